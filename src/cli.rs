@@ -1,10 +1,28 @@
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 
 #[derive(Debug, Parser)]
-#[command(version, about, long_about = None)]
+#[command(version, about, long_about = None, args_conflicts_with_subcommands = true)]
 pub struct CLI {
     /// Config file
-    #[arg(value_name = "config")]
-    pub config: String
+    #[arg(value_name = "config", required = true)]
+    pub config: Option<String>,
+    
+    #[command(subcommand)]
+    pub command: Option<Command>
+}
+
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Run mock Geyser gRPC server, that plays scripted update sequences
+    MockGeyser {
+        /// Script file
+        #[arg(value_name = "script")]
+        script: String,
+        
+        /// Port to listen on
+        #[arg(long, default_value_t = 10000)]
+        port: u16
+    }
 }
\ No newline at end of file
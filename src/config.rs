@@ -1,6 +1,10 @@
-use serde::Deserialize;
-use serde_with::{serde_as, DisplayFromStr};
+use crate::query::SolanaQuery;
+use serde::de::Error;
+use serde::{Deserialize, Deserializer};
+use serde_with::{serde_as, DisplayFromStr, DurationMilliSeconds, DurationSeconds};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::time::Duration;
 use tonic::metadata::AsciiMetadataValue;
 use tonic::transport::Uri;
 
@@ -8,8 +12,83 @@ use tonic::transport::Uri;
 #[derive(Debug, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct Config {
-    pub sources: HashMap<String, GeyserConfig>,
-    pub port: Option<u16>
+    pub sources: HashMap<String, SourceConfig>,
+    pub port: Option<u16>,
+    pub quorum: Option<usize>,
+    pub fallback: Option<FallbackConfig>,
+    #[serde(default)]
+    pub buffer: BufferConfig
+}
+
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct BufferConfig {
+    #[serde(default = "default_buffer_slots")]
+    pub slots: u64,
+    #[serde(default = "default_buffer_memory")]
+    pub memory_mb: usize
+}
+
+
+impl Default for BufferConfig {
+    fn default() -> Self {
+        Self {
+            slots: default_buffer_slots(),
+            memory_mb: default_buffer_memory()
+        }
+    }
+}
+
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FallbackConfig {
+    #[serde(default = "default_fallback_lag_slots")]
+    pub lag_slots: u64,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_fallback_timeout")]
+    pub timeout: Duration
+}
+
+
+#[derive(Debug)]
+pub enum SourceConfig {
+    Geyser(GeyserConfig),
+    File(FileConfig),
+    Rpc(RpcConfig),
+    Spray(SprayConfig)
+}
+
+
+impl<'de> Deserialize<'de> for SourceConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let mut map = serde_yaml::Mapping::deserialize(deserializer)?;
+        
+        // `type` is optional and defaults to `geyser`
+        let kind = match map.remove("type") {
+            Some(kind) => String::deserialize(kind).map_err(D::Error::custom)?,
+            None => "geyser".to_string()
+        };
+        
+        let value = serde_yaml::Value::Mapping(map);
+        match kind.as_str() {
+            "geyser" => GeyserConfig::deserialize(value)
+                .map(SourceConfig::Geyser)
+                .map_err(D::Error::custom),
+            "file" => FileConfig::deserialize(value)
+                .map(SourceConfig::File)
+                .map_err(D::Error::custom),
+            "rpc" => RpcConfig::deserialize(value)
+                .map(SourceConfig::Rpc)
+                .map_err(D::Error::custom),
+            "spray" => SprayConfig::deserialize(value)
+                .map(SourceConfig::Spray)
+                .map_err(D::Error::custom),
+            kind => Err(D::Error::unknown_variant(kind, &["geyser", "file", "rpc", "spray"]))
+        }
+    }
 }
 
 
@@ -22,7 +101,137 @@ pub struct GeyserConfig {
     #[serde_as(as = "Option<DisplayFromStr>")]
     pub x_token: Option<AsciiMetadataValue>,
     #[serde_as(as = "Option<DisplayFromStr>")]
-    pub x_access_token: Option<AsciiMetadataValue>
+    pub x_access_token: Option<AsciiMetadataValue>,
+    #[serde(default)]
+    pub votes: bool,
+    #[serde(default)]
+    pub accounts: bool,
+    #[serde(default)]
+    pub filter_pushdown: bool,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_ping_interval")]
+    pub ping_interval: Duration,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: Duration,
+    pub record: Option<PathBuf>,
+    #[serde(default)]
+    pub mode: GeyserMode,
+    #[serde(default)]
+    pub role: GeyserRole,
+    #[serde(default)]
+    pub lazy: bool
+}
+
+
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeyserMode {
+    #[default]
+    Transactions,
+    Blocks
+}
+
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GeyserRole {
+    #[default]
+    Primary,
+    Backup
+}
+
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FileConfig {
+    pub path: PathBuf,
+    #[serde(default = "default_speed")]
+    pub speed: f64,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default)]
+    pub start_delay: Duration,
+    #[serde(default)]
+    pub votes: bool,
+    #[serde(default)]
+    pub accounts: bool
+}
+
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RpcConfig {
+    pub url: String,
+    #[serde(default)]
+    pub commitment: RpcCommitment,
+    #[serde_as(as = "DurationMilliSeconds<u64>")]
+    #[serde(default = "default_poll_interval")]
+    pub poll_interval: Duration,
+    #[serde(default)]
+    pub votes: bool
+}
+
+
+#[serde_as]
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SprayConfig {
+    pub url: String,
+    pub query: SolanaQuery,
+    #[serde_as(as = "DurationSeconds<u64>")]
+    #[serde(default = "default_idle_timeout")]
+    pub idle_timeout: Duration
+}
+
+
+#[derive(Debug, Default, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RpcCommitment {
+    #[default]
+    Confirmed,
+    Finalized
+}
+
+
+fn default_ping_interval() -> Duration {
+    Duration::from_secs(10)
+}
+
+
+fn default_idle_timeout() -> Duration {
+    Duration::from_secs(30)
+}
+
+
+fn default_speed() -> f64 {
+    1.0
+}
+
+
+fn default_poll_interval() -> Duration {
+    Duration::from_millis(400)
+}
+
+
+fn default_buffer_slots() -> u64 {
+    0
+}
+
+
+fn default_buffer_memory() -> usize {
+    512
+}
+
+
+fn default_fallback_lag_slots() -> u64 {
+    4
+}
+
+
+fn default_fallback_timeout() -> Duration {
+    Duration::from_secs(5)
 }
 
 
@@ -1,4 +1,4 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
 
@@ -8,25 +8,147 @@ pub type Base58Bytes = String;
 pub type JsonString = String;
 
 
+#[derive(Debug, Default, Copy, Clone, Hash, Ord, PartialOrd, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Commitment {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized
+}
+
+
+#[derive(Debug)]
 pub enum DataMessage {
     Block(BlockData),
-    Transaction(TransactionData)
+    Transaction(TransactionData),
+    Account(AccountData),
+    Rollback(RollbackData),
+    SlotStatus(SlotStatusData)
 }
 
 
+impl DataMessage {
+    /// Slot of the data item, `None` for rollbacks
+    pub fn slot(&self) -> Option<u64> {
+        match self {
+            DataMessage::Block(block) => Some(block.slot),
+            DataMessage::Transaction(tx) => Some(tx.slot),
+            DataMessage::Account(acc) => Some(acc.slot),
+            DataMessage::SlotStatus(status) => Some(status.slot),
+            DataMessage::Rollback(_) => None
+        }
+    }
+
+    /// Rough estimate of the memory occupied by the message
+    pub fn estimated_size(&self) -> usize {
+        let size = match self {
+            DataMessage::Block(block) => {
+                block.hash.len() + block.parent_hash.len() + block.rewards.iter().map(|r| {
+                    size_of::<Reward>() + r.pubkey.len()
+                }).sum::<usize>()
+            },
+            DataMessage::Transaction(tx) => {
+                let accounts: usize = tx.accounts.iter().map(|acc| size_of::<Base58Bytes>() + acc.len()).sum();
+                let transaction = tx.transaction.address_table_lookups.len()
+                    + tx.transaction.recent_blockhash.len()
+                    + tx.transaction.signatures.len()
+                    + tx.transaction.signature.as_ref().map_or(0, |sig| sig.len())
+                    + tx.transaction.err.as_ref().map_or(0, |err| err.len())
+                    + tx.transaction.loaded_addresses.len();
+                let instructions: usize = tx.instructions.iter().map(|ins| {
+                    size_of::<Instruction>()
+                        + ins.instruction_address.len() * size_of::<ItemIndex>()
+                        + ins.accounts.len() * size_of::<AccountIndex>()
+                        + ins.data.len()
+                        + ins.binary_data.len()
+                        + ins.error.as_ref().map_or(0, |err| err.len())
+                }).sum();
+                let logs: usize = tx.logs.iter().map(|log| {
+                    size_of::<LogMessage>() + log.program_id.len() + log.message.len()
+                }).sum();
+                let balances = tx.balances.len() * (size_of::<Balance>() + 44);
+                let token_balances = tx.token_balances.len() * (size_of::<TokenBalance>() + 6 * 44);
+                accounts + transaction + instructions + logs + balances + token_balances
+            },
+            DataMessage::Account(acc) => {
+                acc.pubkey.len() + acc.owner.len() + acc.data.len()
+            },
+            DataMessage::Rollback(rollback) => rollback.slots.len() * size_of::<u64>(),
+            DataMessage::SlotStatus(_) => 0
+        };
+        size_of::<DataMessage>() + size
+    }
+}
+
+
+#[derive(Debug)]
 pub struct BlockData {
     pub slot: u64,
     pub hash: Base58Bytes,
     pub parent_slot: u64,
     pub parent_hash: Base58Bytes,
     pub height: Option<u64>,
-    pub timestamp: i64
+    pub timestamp: i64,
+    pub rewards: Vec<Reward>,
+    /// Whether all transactions of the block were received, `None` when unknown
+    pub complete: Option<bool>
+}
+
+
+#[derive(Debug)]
+pub struct Reward {
+    pub pubkey: Base58Bytes,
+    pub lamports: i64,
+    pub post_balance: u64,
+    pub reward_type: Option<RewardType>,
+    pub commission: Option<u8>
+}
+
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RewardType {
+    Fee,
+    Rent,
+    Staking,
+    Voting
+}
+
+
+#[derive(Debug)]
+pub struct AccountData {
+    pub slot: u64,
+    pub pubkey: Base58Bytes,
+    pub lamports: u64,
+    pub owner: Base58Bytes,
+    pub executable: bool,
+    pub rent_epoch: u64,
+    pub data: Vec<u8>,
+    pub write_version: u64,
+    pub txn_signature: Option<Base58Bytes>
+}
+
+
+/// Slots abandoned by the cluster (dead or left on a minor fork)
+#[derive(Debug)]
+pub struct RollbackData {
+    pub slots: Vec<u64>
+}
+
+
+/// Commitment upgrade of a published slot
+#[derive(Debug)]
+pub struct SlotStatusData {
+    pub slot: u64,
+    pub status: Commitment
 }
 
 
 pub type AccountList = Arc<[Base58Bytes]>;
 
 
+#[derive(Debug)]
 pub struct TransactionData {
     pub slot: u64,
     pub transaction_index: ItemIndex,
@@ -34,11 +156,14 @@ pub struct TransactionData {
     pub instructions: Vec<Instruction>,
     pub balances: Vec<Balance>,
     pub token_balances: Vec<TokenBalance>,
+    pub logs: Vec<LogMessage>,
     pub accounts: AccountList
 }
 
 
+#[derive(Debug)]
 pub struct Transaction {
+    pub is_vote: bool,
     pub version: TransactionVersion,
     pub account_keys: usize,
     pub address_table_lookups: JsonString,
@@ -47,14 +172,17 @@ pub struct Transaction {
     pub num_required_signatures: u8,
     pub recent_blockhash: Base58Bytes,
     pub signatures: JsonString,
+    /// First signature, that identifies the transaction
+    pub signature: Option<Base58Bytes>,
     pub err: Option<JsonString>,
     pub compute_units_consumed: Option<u64>,
     pub fee: u64,
     pub loaded_addresses: JsonString,
+    pub has_dropped_log_messages: bool,
 }
 
 
-#[derive(Serialize)]
+#[derive(Debug, Serialize)]
 #[serde(rename_all = "lowercase")]
 pub enum TransactionVersion {
     Legacy,
@@ -63,6 +191,7 @@ pub enum TransactionVersion {
 }
 
 
+#[derive(Debug)]
 pub struct Instruction {
     pub instruction_address: Vec<ItemIndex>,
     pub program_id: AccountIndex,
@@ -70,12 +199,34 @@ pub struct Instruction {
     pub data: Base58Bytes,
     pub binary_data: Vec<u8>,
     pub error: Option<String>,
+    pub compute_units_consumed: Option<u64>,
     pub is_committed: bool,
+    pub has_dropped_log_messages: bool,
     pub account_list: AccountList
 }
 
 
-#[derive(Default, Clone)]
+#[derive(Debug)]
+pub struct LogMessage {
+    pub log_index: ItemIndex,
+    pub instruction_index: ItemIndex,
+    pub instruction_address: Vec<ItemIndex>,
+    pub program_id: Base58Bytes,
+    pub kind: LogKind,
+    pub message: String
+}
+
+
+#[derive(Debug, Copy, Clone, Eq, PartialEq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LogKind {
+    Log,
+    Data,
+    Other
+}
+
+
+#[derive(Debug, Default, Clone)]
 pub struct TokenBalance {
     pub account: Base58Bytes,
     pub pre_mint: Option<Base58Bytes>,
@@ -91,6 +242,7 @@ pub struct TokenBalance {
 }
 
 
+#[derive(Debug)]
 pub struct Balance {
     pub account: Base58Bytes,
     pub pre: u64,
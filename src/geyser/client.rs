@@ -10,20 +10,29 @@ use tonic::transport::{Channel, ClientTlsConfig, Endpoint};
 pub type GeyserClient = api::geyser_client::GeyserClient<InterceptedService<Channel, AuthInterceptor>>;
 
 
-pub async fn create_geyser_client(cfg: GeyserConfig) -> anyhow::Result<GeyserClient> {
-    let channel = Endpoint::from(cfg.url)
+/// Maximum size of a received update message
+pub const MAX_MESSAGE_SIZE: usize = 32 * 1024 * 1024;
+
+
+pub async fn create_geyser_client(cfg: &GeyserConfig) -> anyhow::Result<GeyserClient> {
+    let endpoint = Endpoint::from(cfg.url.clone())
         .tls_config(ClientTlsConfig::new().with_native_roots())
-        .context("failed to configure TLS")?
-        .connect()
-        .await?;
+        .context("failed to configure TLS")?;
+
+    // lazy backups connect only when they are needed
+    let channel = if cfg.lazy {
+        endpoint.connect_lazy()
+    } else {
+        endpoint.connect().await?
+    };
 
     let auth = AuthInterceptor {
-        x_token: cfg.x_token,
-        x_access_token: cfg.x_access_token
+        x_token: cfg.x_token.clone(),
+        x_access_token: cfg.x_access_token.clone()
     };
 
     let client = api::geyser_client::GeyserClient::with_interceptor(channel, auth)
-        .max_decoding_message_size(32 * 1024 * 1024)
+        .max_decoding_message_size(MAX_MESSAGE_SIZE)
         .accept_compressed(CompressionEncoding::Zstd);
 
     Ok(client)
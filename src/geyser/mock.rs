@@ -0,0 +1,465 @@
+use super::api::geyser_server::{Geyser, GeyserServer};
+use super::api::subscribe_update::UpdateOneof;
+use super::api::*;
+use super::solana::storage::confirmed_block::{BlockHeight, Message, MessageHeader, Transaction, TransactionError, TransactionStatusMeta, UnixTimestamp};
+use crate::ingest::{read_recording, ReplayClock};
+use anyhow::Context;
+use serde::Deserialize;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::Sender;
+use tokio_stream::wrappers::ReceiverStream;
+use tokio_stream::StreamExt;
+use tonic::{Request, Response, Status, Streaming};
+use tracing::{debug, info};
+
+
+/// Script of a mock Geyser server.
+///
+/// Every subscription plays the next session from the list.
+/// Once a session is over, the stream stays open and only answers pings.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockScript {
+    #[serde(with = "serde_yaml::with::singleton_map_recursive")]
+    pub sessions: Vec<Vec<MockStep>>
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "snake_case", deny_unknown_fields)]
+pub enum MockStep {
+    /// Send a slot status update
+    Slot(MockSlot),
+    /// Send a block meta update
+    Block(MockBlock),
+    /// Send a block update with transactions
+    FullBlock(MockFullBlock),
+    /// Send a minimal valid transaction
+    Transaction(MockTransaction),
+    /// Send a transaction update without `.meta`
+    MalformedTransaction(MockTransaction),
+    /// Send updates from a recording file
+    Replay(MockReplay),
+    /// Pause for the given number of seconds, still answering pings
+    Sleep(f64),
+    /// Pause for the given number of seconds without sending anything
+    Stall(f64),
+    /// Terminate the stream with an error
+    Disconnect
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockSlot {
+    pub slot: u64,
+    pub parent: Option<u64>,
+    #[serde(default)]
+    pub status: MockSlotStatus,
+    pub dead_error: Option<String>
+}
+
+
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum MockSlotStatus {
+    #[default]
+    Processed,
+    Confirmed,
+    Finalized,
+    Dead
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockBlock {
+    pub slot: u64,
+    pub parent_slot: Option<u64>,
+    pub blockhash: Option<String>,
+    pub parent_blockhash: Option<String>,
+    pub block_time: Option<i64>,
+    pub block_height: Option<u64>,
+    #[serde(default)]
+    pub executed_transaction_count: u64
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockFullBlock {
+    pub slot: u64,
+    pub parent_slot: Option<u64>,
+    /// Number of included transactions
+    #[serde(default)]
+    pub transactions: u64,
+    /// Defaults to the number of included transactions
+    pub executed_transaction_count: Option<u64>
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockTransaction {
+    pub slot: u64,
+    pub index: u64,
+    /// Base58 encoded account keys, the first one is the fee payer
+    #[serde(default)]
+    pub accounts: Vec<String>,
+    #[serde(default)]
+    pub is_vote: bool,
+    #[serde(default)]
+    pub failed: bool
+}
+
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct MockReplay {
+    pub path: PathBuf,
+    #[serde(default)]
+    pub speed: f64
+}
+
+
+impl MockScript {
+    pub fn read(file: impl AsRef<Path>) -> anyhow::Result<Self> {
+        use std::fs::File;
+        use std::io::BufReader;
+        let file = File::open(file)?;
+        let script = serde_yaml::from_reader(BufReader::new(file))?;
+        Ok(script)
+    }
+}
+
+
+/// Mock Geyser service playing scripted update sequences
+#[derive(Clone)]
+pub struct MockGeyser {
+    script: Arc<MockScript>,
+    next_session: Arc<AtomicUsize>
+}
+
+
+impl MockGeyser {
+    pub fn new(script: MockScript) -> Self {
+        Self {
+            script: Arc::new(script),
+            next_session: Arc::new(AtomicUsize::new(0))
+        }
+    }
+
+    pub fn into_server(self) -> GeyserServer<Self> {
+        GeyserServer::new(self)
+    }
+
+    pub async fn serve(self, addr: SocketAddr) -> anyhow::Result<()> {
+        info!("mock geyser is listening on port {}", addr.port());
+        tonic::transport::Server::builder()
+            .add_service(self.into_server())
+            .serve(addr)
+            .await?;
+        Ok(())
+    }
+}
+
+
+type UpdateSender = Sender<Result<SubscribeUpdate, Status>>;
+
+
+#[tonic::async_trait]
+impl Geyser for MockGeyser {
+    type SubscribeStream = ReceiverStream<Result<SubscribeUpdate, Status>>;
+
+    async fn subscribe(
+        &self,
+        request: Request<Streaming<SubscribeRequest>>
+    ) -> Result<Response<Self::SubscribeStream>, Status>
+    {
+        let session = self.next_session.fetch_add(1, Ordering::SeqCst);
+        let steps = self.script.sessions.get(session).cloned().unwrap_or_default();
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let stalled = Arc::new(AtomicBool::new(false));
+
+        debug!(session, "subscribed");
+
+        tokio::spawn(answer_pings(request.into_inner(), tx.clone(), stalled.clone()));
+        tokio::spawn(async move {
+            if let Err(err) = play_session(steps, &tx, &stalled).await {
+                let _ = tx.send(Err(Status::internal(format!("{:?}", err)))).await;
+            }
+            debug!(session, "session is over");
+        });
+
+        Ok(Response::new(ReceiverStream::new(rx)))
+    }
+
+    async fn ping(&self, _request: Request<PingRequest>) -> Result<Response<PongResponse>, Status> {
+        Ok(Response::new(PongResponse { count: 0 }))
+    }
+
+    async fn get_latest_blockhash(
+        &self,
+        _request: Request<GetLatestBlockhashRequest>
+    ) -> Result<Response<GetLatestBlockhashResponse>, Status>
+    {
+        Err(Status::unimplemented("not supported by mock"))
+    }
+
+    async fn get_block_height(
+        &self,
+        _request: Request<GetBlockHeightRequest>
+    ) -> Result<Response<GetBlockHeightResponse>, Status>
+    {
+        Err(Status::unimplemented("not supported by mock"))
+    }
+
+    async fn get_slot(&self, _request: Request<GetSlotRequest>) -> Result<Response<GetSlotResponse>, Status> {
+        Err(Status::unimplemented("not supported by mock"))
+    }
+
+    async fn is_blockhash_valid(
+        &self,
+        _request: Request<IsBlockhashValidRequest>
+    ) -> Result<Response<IsBlockhashValidResponse>, Status>
+    {
+        Err(Status::unimplemented("not supported by mock"))
+    }
+
+    async fn get_version(
+        &self,
+        _request: Request<GetVersionRequest>
+    ) -> Result<Response<GetVersionResponse>, Status>
+    {
+        Ok(Response::new(GetVersionResponse {
+            version: "mock".to_string()
+        }))
+    }
+}
+
+
+async fn answer_pings(
+    mut requests: Streaming<SubscribeRequest>,
+    tx: UpdateSender,
+    stalled: Arc<AtomicBool>
+) {
+    while let Ok(Some(req)) = requests.try_next().await {
+        let Some(ping) = req.ping else {
+            continue
+        };
+        if stalled.load(Ordering::SeqCst) {
+            continue
+        }
+        let pong = UpdateOneof::Pong(SubscribeUpdatePong {
+            id: ping.id
+        });
+        if tx.send(Ok(update(pong))).await.is_err() {
+            return
+        }
+    }
+}
+
+
+async fn play_session(steps: Vec<MockStep>, tx: &UpdateSender, stalled: &AtomicBool) -> anyhow::Result<()> {
+    for step in steps {
+        let upd = match step {
+            MockStep::Slot(slot) => UpdateOneof::Slot(SubscribeUpdateSlot {
+                slot: slot.slot,
+                parent: slot.parent,
+                status: match slot.status {
+                    MockSlotStatus::Processed => SlotStatus::SlotProcessed,
+                    MockSlotStatus::Confirmed => SlotStatus::SlotConfirmed,
+                    MockSlotStatus::Finalized => SlotStatus::SlotFinalized,
+                    MockSlotStatus::Dead => SlotStatus::SlotDead
+                } as i32,
+                dead_error: slot.dead_error
+            }),
+            MockStep::Block(block) => UpdateOneof::BlockMeta(mock_block(block)),
+            MockStep::FullBlock(block) => UpdateOneof::Block(mock_full_block(block)),
+            MockStep::Transaction(tx) => UpdateOneof::Transaction(mock_transaction(tx, true)),
+            MockStep::MalformedTransaction(tx) => UpdateOneof::Transaction(mock_transaction(tx, false)),
+            MockStep::Replay(replay) => {
+                let mut updates = read_recording(&replay.path)?;
+                let mut clock = ReplayClock::new(replay.speed);
+                while let Some(upd) = updates.recv().await {
+                    let upd = upd?;
+                    clock.wait(upd.created_at).await;
+                    if tx.send(Ok(upd)).await.is_err() {
+                        return Ok(())
+                    }
+                }
+                continue
+            },
+            MockStep::Sleep(secs) => {
+                tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+                continue
+            },
+            MockStep::Stall(secs) => {
+                stalled.store(true, Ordering::SeqCst);
+                tokio::time::sleep(Duration::from_secs_f64(secs)).await;
+                stalled.store(false, Ordering::SeqCst);
+                continue
+            },
+            MockStep::Disconnect => {
+                let _ = tx.send(Err(Status::unavailable("scripted disconnect"))).await;
+                return Ok(())
+            }
+        };
+        if tx.send(Ok(update(upd))).await.is_err() {
+            return Ok(())
+        }
+    }
+    Ok(())
+}
+
+
+fn update(upd: UpdateOneof) -> SubscribeUpdate {
+    SubscribeUpdate {
+        filters: Vec::new(),
+        update_oneof: Some(upd),
+        created_at: Some(SystemTime::now().into())
+    }
+}
+
+
+pub fn mock_block(block: MockBlock) -> SubscribeUpdateBlockMeta {
+    let parent_slot = block.parent_slot.unwrap_or(block.slot.saturating_sub(1));
+    SubscribeUpdateBlockMeta {
+        slot: block.slot,
+        blockhash: block.blockhash.unwrap_or_else(|| mock_hash(block.slot)),
+        rewards: None,
+        block_time: Some(UnixTimestamp {
+            timestamp: block.block_time.unwrap_or_else(|| chrono::Utc::now().timestamp())
+        }),
+        block_height: Some(BlockHeight {
+            block_height: block.block_height.unwrap_or(block.slot)
+        }),
+        parent_slot,
+        parent_blockhash: block.parent_blockhash.unwrap_or_else(|| mock_hash(parent_slot)),
+        executed_transaction_count: block.executed_transaction_count,
+        entries_count: 0
+    }
+}
+
+
+pub fn mock_full_block(block: MockFullBlock) -> SubscribeUpdateBlock {
+    let meta = mock_block(MockBlock {
+        slot: block.slot,
+        parent_slot: block.parent_slot,
+        blockhash: None,
+        parent_blockhash: None,
+        block_time: None,
+        block_height: None,
+        executed_transaction_count: block.executed_transaction_count.unwrap_or(block.transactions)
+    });
+    let transactions = (0..block.transactions).filter_map(|index| {
+        let tx = MockTransaction {
+            slot: block.slot,
+            index,
+            accounts: Vec::new(),
+            is_vote: false,
+            failed: false
+        };
+        mock_transaction(tx, true).transaction
+    }).collect();
+    SubscribeUpdateBlock {
+        slot: meta.slot,
+        blockhash: meta.blockhash,
+        rewards: meta.rewards,
+        block_time: meta.block_time,
+        block_height: meta.block_height,
+        parent_slot: meta.parent_slot,
+        parent_blockhash: meta.parent_blockhash,
+        executed_transaction_count: meta.executed_transaction_count,
+        transactions,
+        updated_account_count: 0,
+        accounts: Vec::new(),
+        entries_count: 0,
+        entries: Vec::new()
+    }
+}
+
+
+pub fn mock_transaction(tx: MockTransaction, with_meta: bool) -> SubscribeUpdateTransaction {
+    let mut signature = vec![0; 64];
+    signature[..8].copy_from_slice(&tx.slot.to_le_bytes());
+    signature[8..16].copy_from_slice(&tx.index.to_le_bytes());
+
+    let account_keys: Vec<Vec<u8>> = if tx.accounts.is_empty() {
+        vec![signature[..32].to_vec()]
+    } else {
+        tx.accounts.iter().map(|a| bs58::decode(a).into_vec().unwrap_or_default()).collect()
+    };
+
+    let meta = with_meta.then(|| TransactionStatusMeta {
+        err: tx.failed.then(|| TransactionError {
+            err: bincode::serialize(
+                &solana_transaction_error::TransactionError::AccountNotFound
+            ).expect("serialization is infallible")
+        }),
+        fee: 5000,
+        pre_balances: vec![0; account_keys.len()],
+        post_balances: vec![0; account_keys.len()],
+        log_messages_none: true,
+        ..TransactionStatusMeta::default()
+    });
+
+    SubscribeUpdateTransaction {
+        slot: tx.slot,
+        transaction: Some(SubscribeUpdateTransactionInfo {
+            signature: signature.clone(),
+            is_vote: tx.is_vote,
+            transaction: Some(Transaction {
+                signatures: vec![signature],
+                message: Some(Message {
+                    header: Some(MessageHeader {
+                        num_required_signatures: 1,
+                        num_readonly_signed_accounts: 0,
+                        num_readonly_unsigned_accounts: 0
+                    }),
+                    account_keys,
+                    recent_blockhash: vec![0; 32],
+                    instructions: Vec::new(),
+                    versioned: false,
+                    address_table_lookups: Vec::new()
+                })
+            }),
+            meta,
+            index: tx.index
+        })
+    }
+}
+
+
+fn mock_hash(slot: u64) -> String {
+    let mut hash = [0u8; 32];
+    hash[..8].copy_from_slice(&slot.to_le_bytes());
+    bs58::encode(hash).into_string()
+}
+
+
+pub async fn run_mock_geyser(script: &Path, port: u16) -> anyhow::Result<()> {
+    let script = MockScript::read(script).context("failed to read mock script")?;
+    MockGeyser::new(script).serve(([0, 0, 0, 0], port).into()).await
+}
+
+/// Serves the given YAML script on a random local port
+/// and returns a client connected to it
+#[cfg(test)]
+pub async fn connect_mock_geyser(script: &str) -> super::GeyserClient {
+    use tonic::transport::server::TcpIncoming;
+    let script: MockScript = serde_yaml::from_str(script).unwrap();
+    let incoming = TcpIncoming::bind(([127, 0, 0, 1], 0).into()).unwrap();
+    let addr = incoming.local_addr().unwrap();
+    tokio::spawn(
+        tonic::transport::Server::builder()
+            .add_service(MockGeyser::new(script).into_server())
+            .serve_with_incoming(incoming)
+    );
+    let cfg = serde_yaml::from_str(&format!("url: http://{}", addr)).unwrap();
+    super::create_geyser_client(&cfg).await.unwrap()
+}
\ No newline at end of file
@@ -1,8 +1,10 @@
 mod auth_interceptor;
 mod client;
+mod mock;
 
 
 pub use client::*;
+pub use mock::*;
 
 
 pub mod api {
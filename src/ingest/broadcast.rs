@@ -0,0 +1,147 @@
+use super::buffer::{BufferOptions, DataChunk, SlotBuffer};
+use crate::data::{Commitment, DataMessage};
+use anyhow::anyhow;
+use std::sync::{Arc, Mutex, MutexGuard};
+use tokio::sync::broadcast::{Receiver, Sender};
+
+
+/// Buffered messages followed by the receiver of live ones
+pub type BufferedSubscription = (Vec<Arc<DataMessage>>, Receiver<Arc<DataMessage>>);
+
+
+/// Per-commitment fan-out of published data messages
+#[derive(Clone)]
+pub struct Broadcast {
+    processed: Arc<Level>,
+    confirmed: Arc<Level>,
+    finalized: Arc<Level>
+}
+
+
+struct Level {
+    sender: Sender<Arc<DataMessage>>,
+    /// `None` when the buffer is disabled
+    buffer: Option<Mutex<SlotBuffer>>
+}
+
+
+impl Level {
+    fn new(capacity: usize, buffer: Option<SlotBuffer>) -> Arc<Self> {
+        Arc::new(Self {
+            sender: Sender::new(capacity),
+            buffer: buffer.map(Mutex::new)
+        })
+    }
+
+    fn buffer(&self) -> anyhow::Result<MutexGuard<'_, SlotBuffer>> {
+        match self.buffer.as_ref() {
+            Some(buffer) => Ok(buffer.lock().unwrap()),
+            None => Err(anyhow!("the buffer of recent slots is disabled"))
+        }
+    }
+}
+
+
+impl Broadcast {
+    pub fn new(capacity: usize, buffer: BufferOptions) -> Self {
+        let new_buffer = || (buffer.max_slots > 0).then(|| SlotBuffer::new(buffer));
+        Self {
+            // processed level receives all transactions
+            processed: Level::new(capacity, new_buffer().map(SlotBuffer::with_signature_index)),
+            confirmed: Level::new(capacity, new_buffer()),
+            finalized: Level::new(capacity, new_buffer())
+        }
+    }
+
+    pub fn send(&self, commitment: Commitment, msg: Arc<DataMessage>) {
+        let level = self.level(commitment);
+        if let Some(buffer) = level.buffer.as_ref() {
+            // resumed subscriptions read the buffer under the same lock,
+            // so that they neither miss nor repeat messages
+            let mut buffer = buffer.lock().unwrap();
+            buffer.push(msg.clone());
+            let _ = level.sender.send(msg);
+        } else {
+            let _ = level.sender.send(msg);
+        }
+    }
+
+    pub fn subscribe(&self, commitment: Commitment) -> Receiver<Arc<DataMessage>> {
+        self.level(commitment).sender.subscribe()
+    }
+
+    /// Returns buffered messages starting from the given slot
+    /// and a receiver for the messages published after them.
+    pub fn subscribe_from(
+        &self,
+        commitment: Commitment,
+        from_slot: u64
+    ) -> anyhow::Result<BufferedSubscription>
+    {
+        let level = self.level(commitment);
+        let buffer = level.buffer()?;
+        let buffered = read_buffer(&buffer, from_slot)?;
+        Ok((buffered, level.sender.subscribe()))
+    }
+
+    /// Reads buffered data messages of the given slot range, see [SlotBuffer::read_data].
+    ///
+    /// Starts from the first available slot, if `from_slot` is not given.
+    pub fn read_data(
+        &self,
+        commitment: Commitment,
+        from_slot: Option<u64>,
+        to_slot: u64,
+        limit: usize
+    ) -> anyhow::Result<DataChunk>
+    {
+        let buffer = self.level(commitment).buffer()?;
+        let from_slot = from_slot.unwrap_or_else(|| buffer.first_available_slot());
+        buffer.read_data(from_slot, to_slot, limit).ok_or_else(|| not_available(&buffer, from_slot))
+    }
+
+    /// Looks up a recently published transaction by signature
+    pub fn get_transaction(&self, signature: &str) -> anyhow::Result<Option<Arc<DataMessage>>> {
+        Ok(self.processed.buffer()?.get_transaction(signature))
+    }
+
+    /// Returns buffered processed messages starting from the slot of the earliest
+    /// recently published transaction with one of the given signatures
+    /// and a receiver for the messages published after them.
+    pub fn subscribe_signatures(&self, signatures: &[String]) -> BufferedSubscription {
+        let level = &self.processed;
+        let Ok(buffer) = level.buffer() else {
+            return (Vec::new(), level.sender.subscribe())
+        };
+        let from_slot = signatures.iter()
+            .filter_map(|sig| buffer.get_transaction(sig))
+            .filter_map(|msg| msg.slot())
+            .min();
+        let buffered = from_slot
+            .and_then(|slot| buffer.read_from(slot.max(buffer.first_available_slot())))
+            .unwrap_or_default();
+        (buffered, level.sender.subscribe())
+    }
+
+    fn level(&self, commitment: Commitment) -> &Level {
+        match commitment {
+            Commitment::Processed => &self.processed,
+            Commitment::Confirmed => &self.confirmed,
+            Commitment::Finalized => &self.finalized
+        }
+    }
+}
+
+
+fn read_buffer(buffer: &SlotBuffer, from_slot: u64) -> anyhow::Result<Vec<Arc<DataMessage>>> {
+    buffer.read_from(from_slot).ok_or_else(|| not_available(buffer, from_slot))
+}
+
+
+fn not_available(buffer: &SlotBuffer, from_slot: u64) -> anyhow::Error {
+    anyhow!(
+        "slot {} is no longer available, the first available slot is {}",
+        from_slot,
+        buffer.first_available_slot()
+    )
+}
\ No newline at end of file
@@ -0,0 +1,179 @@
+use crate::data::DataMessage;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::sync::Arc;
+
+
+#[derive(Debug, Copy, Clone)]
+pub struct BufferOptions {
+    /// Maximum number of recent slots to keep
+    pub max_slots: u64,
+    /// Maximum estimated size of kept messages in bytes
+    pub max_bytes: usize
+}
+
+
+/// Buffered data messages of consecutive slots
+pub struct DataChunk {
+    /// Messages along with their slot
+    pub messages: Vec<(u64, Arc<DataMessage>)>,
+    /// Messages of all slots up to the given one were read
+    pub last_slot: u64,
+    /// Whether the end of the requested range was reached
+    pub complete: bool
+}
+
+
+/// Keeps published messages of the recent slots
+pub struct SlotBuffer {
+    options: BufferOptions,
+    entries: VecDeque<Entry>,
+    bytes: usize,
+    /// Highest slot, that was published
+    head: u64,
+    /// Highest slot, that was (partially) evicted
+    evicted: Option<u64>,
+    /// Buffered transactions by their first signature
+    signatures: Option<HashMap<String, Arc<DataMessage>>>,
+    /// Rolled back slots, that might still have buffered data
+    rolled_back: HashSet<u64>
+}
+
+
+struct Entry {
+    /// Head slot at the time of publication.
+    /// Keeps entries ordered, although commitment updates and rollbacks refer to older slots.
+    slot: u64,
+    size: usize,
+    msg: Arc<DataMessage>
+}
+
+
+impl SlotBuffer {
+    pub fn new(options: BufferOptions) -> Self {
+        Self {
+            options,
+            entries: VecDeque::new(),
+            bytes: 0,
+            head: 0,
+            evicted: None,
+            signatures: None,
+            rolled_back: HashSet::new()
+        }
+    }
+
+    /// Enables lookup of buffered transactions by signature
+    pub fn with_signature_index(mut self) -> Self {
+        self.signatures = Some(HashMap::new());
+        self
+    }
+
+    pub fn push(&mut self, msg: Arc<DataMessage>) {
+        if let DataMessage::Block(_) | DataMessage::Transaction(_) | DataMessage::Account(_) = msg.as_ref() {
+            self.head = self.head.max(msg.slot().unwrap_or_default());
+        }
+        if let DataMessage::Rollback(rollback) = msg.as_ref() {
+            self.rolled_back.extend(rollback.slots.iter().copied());
+        }
+        if let Some(index) = self.signatures.as_mut() {
+            match msg.as_ref() {
+                DataMessage::Transaction(tx) => {
+                    if let Some(signature) = tx.transaction.signature.clone() {
+                        index.insert(signature, msg.clone());
+                    }
+                },
+                DataMessage::Rollback(rollback) => {
+                    index.retain(|_, tx| !tx.slot().is_some_and(|slot| rollback.slots.contains(&slot)));
+                },
+                _ => {}
+            }
+        }
+        let slot = self.head;
+        let size = msg.estimated_size();
+        self.bytes += size;
+        self.entries.push_back(Entry {
+            slot,
+            size,
+            msg
+        });
+        self.evict();
+    }
+
+    fn evict(&mut self) {
+        while let Some(entry) = self.entries.front() {
+            let too_old = entry.slot + self.options.max_slots <= self.head;
+            if !too_old && self.bytes <= self.options.max_bytes {
+                return
+            }
+            self.evicted = Some(entry.slot);
+            self.rolled_back.retain(|slot| *slot > entry.slot);
+            self.bytes -= entry.size;
+            if let (Some(index), DataMessage::Transaction(tx)) = (self.signatures.as_mut(), entry.msg.as_ref()) {
+                let signature = tx.transaction.signature.as_deref().unwrap_or_default();
+                // the same transaction might have been published again for another fork
+                if index.get(signature).is_some_and(|indexed| Arc::ptr_eq(indexed, &entry.msg)) {
+                    index.remove(signature);
+                }
+            }
+            self.entries.pop_front();
+        }
+    }
+
+    /// Returns buffered messages starting from the given slot.
+    ///
+    /// Returns `None` if some messages of the requested slots were already evicted.
+    pub fn read_from(&self, from_slot: u64) -> Option<Vec<Arc<DataMessage>>> {
+        if self.evicted.is_some_and(|evicted| from_slot <= evicted) {
+            return None
+        }
+        let start = self.entries.partition_point(|entry| entry.slot < from_slot);
+        Some(self.entries.range(start..).map(|entry| entry.msg.clone()).collect())
+    }
+
+    /// Returns block, transaction and account messages of the whole slots within the given range,
+    /// stopping at the first slot boundary after `limit` messages.
+    /// Data of rolled back slots is excluded.
+    ///
+    /// Returns `None` if some messages of the requested slots were already evicted.
+    pub fn read_data(&self, from_slot: u64, to_slot: u64, limit: usize) -> Option<DataChunk> {
+        if self.evicted.is_some_and(|evicted| from_slot <= evicted) {
+            return None
+        }
+        let start = self.entries.partition_point(|entry| entry.slot < from_slot);
+        let mut messages: Vec<(u64, Arc<DataMessage>)> = Vec::new();
+        for entry in self.entries.range(start..) {
+            if entry.slot > to_slot {
+                break
+            }
+            if messages.len() >= limit && messages.last().is_some_and(|(slot, _)| *slot < entry.slot) {
+                return Some(DataChunk {
+                    messages,
+                    last_slot: entry.slot - 1,
+                    complete: false
+                })
+            }
+            let is_data = matches!(
+                entry.msg.as_ref(),
+                DataMessage::Block(_) | DataMessage::Transaction(_) | DataMessage::Account(_)
+            );
+            if is_data && !entry.msg.slot().is_some_and(|slot| self.rolled_back.contains(&slot)) {
+                messages.push((entry.slot, entry.msg.clone()));
+            }
+        }
+        Some(DataChunk {
+            messages,
+            last_slot: to_slot.min(self.head).max(from_slot.saturating_sub(1)),
+            complete: true
+        })
+    }
+
+    /// Returns the buffered transaction with the given signature,
+    /// unless its slot was rolled back
+    pub fn get_transaction(&self, signature: &str) -> Option<Arc<DataMessage>> {
+        self.signatures.as_ref()?.get(signature).cloned()
+    }
+
+    /// Lowest slot, that can be requested
+    pub fn first_available_slot(&self) -> u64 {
+        self.evicted.map_or(0, |evicted| evicted + 1)
+    }
+}
\ No newline at end of file
@@ -0,0 +1,423 @@
+use crate::data::{Commitment, DataMessage, RollbackData, SlotStatusData};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use tracing::warn;
+
+
+/// Upper bound on the number of tracked slots,
+/// protects against unbounded growth when slot status updates are not coming.
+const MAX_TRACKED_SLOTS: usize = 1000;
+
+
+struct SlotEntry {
+    parent: Option<u64>,
+    status: Commitment,
+    has_block: bool,
+    messages: Vec<Arc<DataMessage>>
+}
+
+
+impl SlotEntry {
+    fn new() -> Self {
+        Self {
+            parent: None,
+            status: Commitment::Processed,
+            has_block: false,
+            messages: Vec::new()
+        }
+    }
+}
+
+
+/// Holds processed data messages until their slots reach
+/// `confirmed` and `finalized` commitment levels.
+///
+/// Also detects slots abandoned by the cluster and issues rollback messages for them,
+/// as well as commitment upgrade messages for published slots.
+pub struct CommitmentTracker {
+    slots: BTreeMap<u64, SlotEntry>,
+    upgrades: Vec<(u64, Commitment)>,
+    last_confirmed_release: u64,
+    last_finalized_release: u64,
+    finalized_slot: u64,
+    /// Slots below this one won't receive any more data
+    closed_below: u64
+}
+
+
+impl CommitmentTracker {
+    pub fn new() -> Self {
+        Self {
+            slots: BTreeMap::new(),
+            upgrades: Vec::new(),
+            last_confirmed_release: 0,
+            last_finalized_release: 0,
+            finalized_slot: 0,
+            closed_below: 0
+        }
+    }
+
+    pub fn push(
+        &mut self,
+        msg: Arc<DataMessage>,
+        emit: &mut impl FnMut(Commitment, Arc<DataMessage>)
+    ) {
+        let slot = match msg.as_ref() {
+            DataMessage::Block(block) => block.slot,
+            DataMessage::Transaction(tx) => tx.slot,
+            DataMessage::Account(acc) => acc.slot,
+            DataMessage::Rollback(_) | DataMessage::SlotStatus(_) => return
+        };
+
+        if slot <= self.finalized_slot {
+            return;
+        }
+
+        if slot <= self.last_confirmed_release {
+            // the block of the slot (if any) was already released and can't be followed by more data
+            warn!(slot, "dropping data of an already released slot");
+            crate::metrics::register_unreleased_messages(1);
+            return;
+        }
+
+        let entry = self.slots.entry(slot).or_insert_with(SlotEntry::new);
+
+        if let DataMessage::Block(block) = msg.as_ref() {
+            entry.parent = Some(block.parent_slot);
+            entry.has_block = true;
+            let status = entry.status;
+            entry.messages.push(msg);
+            self.propagate_status(slot, status);
+            self.release(emit);
+            self.emit_upgrades(emit);
+        } else {
+            entry.messages.push(msg);
+        }
+
+        self.enforce_limit();
+    }
+
+    pub fn update_status(
+        &mut self,
+        slot: u64,
+        parent: Option<u64>,
+        status: Commitment,
+        emit: &mut impl FnMut(Commitment, Arc<DataMessage>)
+    ) {
+        if slot <= self.finalized_slot {
+            return;
+        }
+
+        let entry = self.slots.entry(slot).or_insert_with(SlotEntry::new);
+        if entry.parent.is_none() {
+            entry.parent = parent;
+        }
+
+        self.propagate_status(slot, status);
+
+        if status == Commitment::Finalized {
+            self.finalized_slot = slot;
+        }
+
+        self.release(emit);
+        self.emit_upgrades(emit);
+
+        if status == Commitment::Finalized {
+            self.rollback_abandoned(slot, emit);
+        }
+
+        self.prune();
+        self.enforce_limit();
+    }
+
+    /// Registers, that slots below the given one won't receive any more data,
+    /// so that they can be released without a block
+    pub fn close_slots(&mut self, below: u64, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        if below <= self.closed_below {
+            return;
+        }
+        self.closed_below = below;
+        self.release(emit);
+    }
+
+    pub fn mark_dead(&mut self, slot: u64, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        if slot <= self.finalized_slot {
+            return;
+        }
+
+        let mut dead = vec![slot];
+        for (child, entry) in self.slots.range(slot + 1..) {
+            if entry.parent.is_some_and(|parent| dead.contains(&parent)) {
+                dead.push(*child);
+            }
+        }
+
+        let rolled_back = dead.into_iter().filter(|slot| {
+            self.slots.remove(slot).is_some_and(|entry| !entry.messages.is_empty())
+        }).collect();
+
+        self.emit_rollback(rolled_back, emit);
+    }
+
+    /// Rolls back published slots, that are not ancestors of the given finalized slot.
+    ///
+    /// Only the range of slots with fully known ancestry is considered.
+    fn rollback_abandoned(&mut self, finalized: u64, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        let mut bottom = finalized;
+        let mut next = Some(finalized);
+        while let Some(slot) = next {
+            bottom = slot;
+            next = self.slots.get(&slot).and_then(|entry| entry.parent);
+        }
+
+        if bottom == finalized {
+            return;
+        }
+
+        let rolled_back = self.slots.range(bottom + 1..finalized).filter_map(|(slot, entry)| {
+            (entry.status < Commitment::Finalized && !entry.messages.is_empty()).then_some(*slot)
+        }).collect();
+
+        self.emit_rollback(rolled_back, emit);
+    }
+
+    fn emit_rollback(&mut self, slots: Vec<u64>, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        if slots.is_empty() {
+            return;
+        }
+        crate::metrics::register_rollback(slots.len());
+        emit(Commitment::Processed, Arc::new(DataMessage::Rollback(RollbackData {
+            slots
+        })))
+    }
+
+    /// Sets the status of the given slot and all its known ancestors
+    fn propagate_status(&mut self, slot: u64, status: Commitment) {
+        let mut next = Some(slot);
+        while let Some(slot) = next {
+            let Some(entry) = self.slots.get_mut(&slot) else {
+                return
+            };
+            if entry.status < status {
+                entry.status = status;
+                if !entry.messages.is_empty() {
+                    self.upgrades.push((slot, status));
+                }
+            }
+            next = entry.parent;
+        }
+    }
+
+    fn release(&mut self, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        for level in [Commitment::Confirmed, Commitment::Finalized] {
+            let last_release = match level {
+                Commitment::Confirmed => &mut self.last_confirmed_release,
+                _ => &mut self.last_finalized_release
+            };
+            for (slot, entry) in self.slots.range(*last_release + 1..) {
+                if entry.status < level {
+                    continue
+                }
+                if !entry.has_block && *slot >= self.closed_below {
+                    if *slot > self.finalized_slot {
+                        // wait for the block to arrive or for the slot to be closed without one
+                        break
+                    } else {
+                        continue
+                    }
+                }
+                for msg in entry.messages.iter() {
+                    emit(level, msg.clone())
+                }
+                *last_release = *slot;
+            }
+        }
+    }
+
+    fn emit_upgrades(&mut self, emit: &mut impl FnMut(Commitment, Arc<DataMessage>)) {
+        self.upgrades.sort();
+        for (slot, status) in self.upgrades.drain(..) {
+            let msg = Arc::new(DataMessage::SlotStatus(SlotStatusData {
+                slot,
+                status
+            }));
+            if status == Commitment::Finalized {
+                emit(Commitment::Confirmed, msg.clone());
+            }
+            emit(Commitment::Processed, msg);
+        }
+    }
+
+    /// Drops slots that are either finalized or abandoned
+    fn prune(&mut self) {
+        self.slots = self.slots.split_off(&(self.finalized_slot + 1));
+    }
+
+    fn enforce_limit(&mut self) {
+        while self.slots.len() > MAX_TRACKED_SLOTS {
+            let (slot, entry) = self.slots.pop_first().unwrap();
+            if slot > self.last_finalized_release && !entry.messages.is_empty() {
+                warn!(
+                    slot,
+                    messages = entry.messages.len(),
+                    "dropping data of a slot, that was not confirmed in time"
+                );
+                crate::metrics::register_unreleased_messages(entry.messages.len());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::testing::{block, data, describe, tx};
+
+    /// Returns data messages released at `confirmed` level
+    fn confirmed(
+        tracker: &mut CommitmentTracker,
+        f: impl FnOnce(&mut CommitmentTracker, &mut dyn FnMut(Commitment, Arc<DataMessage>))
+    ) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut emit = |level, msg: Arc<DataMessage>| {
+            if level == Commitment::Confirmed {
+                out.push(describe(&msg));
+            }
+        };
+        f(tracker, &mut emit);
+        out
+    }
+
+    /// Returns all emitted messages prefixed with their level
+    fn emitted(
+        tracker: &mut CommitmentTracker,
+        f: impl FnOnce(&mut CommitmentTracker, &mut dyn FnMut(Commitment, Arc<DataMessage>))
+    ) -> Vec<String> {
+        let mut out = Vec::new();
+        let mut emit = |level, msg: Arc<DataMessage>| {
+            out.push(format!("{:?}: {}", level, describe(&msg)));
+        };
+        f(tracker, &mut emit);
+        out
+    }
+
+    #[test]
+    fn release_with_block() {
+        let mut tracker = CommitmentTracker::new();
+        let out = confirmed(&mut tracker, |t, mut emit| {
+            t.push(data(tx(100, 0)), &mut emit);
+            t.push(data(block(100, 99)), &mut emit);
+            t.update_status(100, Some(99), Commitment::Confirmed, &mut emit);
+        });
+        assert_eq!(out, vec!["tx 100:0", "block 100"]);
+    }
+
+    #[test]
+    fn release_closed_slot_without_block() {
+        let mut tracker = CommitmentTracker::new();
+        let out = confirmed(&mut tracker, |t, mut emit| {
+            t.push(data(tx(101, 0)), &mut emit);
+            t.push(data(tx(102, 0)), &mut emit);
+            t.push(data(block(102, 101)), &mut emit);
+            t.update_status(102, Some(101), Commitment::Confirmed, &mut emit);
+        });
+        assert!(out.is_empty(), "slot 101 is still open");
+
+        let out = confirmed(&mut tracker, |t, mut emit| {
+            t.close_slots(102, &mut emit);
+        });
+        assert_eq!(out, vec!["tx 101:0", "tx 102:0", "block 102"]);
+    }
+
+    #[test]
+    fn drop_late_data_of_released_slot() {
+        let mut tracker = CommitmentTracker::new();
+        let out = emitted(&mut tracker, |t, mut emit| {
+            t.push(data(tx(100, 0)), &mut emit);
+            t.push(data(block(100, 99)), &mut emit);
+            t.update_status(100, Some(99), Commitment::Confirmed, &mut emit);
+            t.push(data(tx(100, 1)), &mut emit);
+            t.update_status(100, Some(99), Commitment::Finalized, &mut emit);
+        });
+        assert!(!out.iter().any(|msg| msg.ends_with("tx 100:1")), "{:?}", out);
+        assert!(out.contains(&"Finalized: block 100".to_string()));
+    }
+
+    #[test]
+    fn drop_stalled_slots_over_limit() {
+        let mut tracker = CommitmentTracker::new();
+        let out = confirmed(&mut tracker, |t, mut emit| {
+            for slot in 1..=MAX_TRACKED_SLOTS as u64 + 10 {
+                t.push(data(tx(slot, 0)), &mut emit);
+                t.push(data(block(slot, slot - 1)), &mut emit);
+            }
+        });
+        assert!(out.is_empty());
+        assert_eq!(tracker.slots.len(), MAX_TRACKED_SLOTS);
+        assert_eq!(tracker.slots.first_key_value().map(|(slot, _)| *slot), Some(11));
+    }
+
+    #[test]
+    fn mark_dead_with_descendants() {
+        let mut tracker = CommitmentTracker::new();
+        let out = emitted(&mut tracker, |t, mut emit| {
+            t.push(data(tx(100, 0)), &mut emit);
+            t.push(data(block(100, 99)), &mut emit);
+            t.push(data(block(101, 100)), &mut emit);
+            t.push(data(block(102, 101)), &mut emit);
+            t.push(data(block(103, 99)), &mut emit);
+            t.mark_dead(100, &mut emit);
+            t.update_status(103, Some(99), Commitment::Confirmed, &mut emit);
+        });
+        assert_eq!(out, vec!["Processed: rollback [100, 101, 102]", "Confirmed: block 103", "Processed: Confirmed 103"]);
+    }
+
+    #[test]
+    fn rollback_abandoned_fork() {
+        let mut tracker = CommitmentTracker::new();
+        let out = emitted(&mut tracker, |t, mut emit| {
+            t.push(data(block(100, 99)), &mut emit);
+            t.push(data(tx(101, 0)), &mut emit);
+            t.push(data(block(101, 100)), &mut emit);
+            t.push(data(block(102, 100)), &mut emit);
+            t.update_status(102, Some(100), Commitment::Finalized, &mut emit);
+        });
+        assert_eq!(out, vec![
+            "Confirmed: block 100",
+            "Confirmed: block 102",
+            "Finalized: block 100",
+            "Finalized: block 102",
+            "Confirmed: Finalized 100",
+            "Processed: Finalized 100",
+            "Confirmed: Finalized 102",
+            "Processed: Finalized 102",
+            "Processed: rollback [101]"
+        ]);
+    }
+
+    #[test]
+    fn upgrade_notifications() {
+        let mut tracker = CommitmentTracker::new();
+        let out = emitted(&mut tracker, |t, mut emit| {
+            t.push(data(block(100, 99)), &mut emit);
+            t.push(data(tx(101, 0)), &mut emit);
+            t.push(data(block(101, 100)), &mut emit);
+            t.update_status(100, Some(99), Commitment::Confirmed, &mut emit);
+            t.update_status(101, Some(100), Commitment::Finalized, &mut emit);
+        });
+        assert_eq!(out, vec![
+            "Confirmed: block 100",
+            "Processed: Confirmed 100",
+            "Confirmed: tx 101:0",
+            "Confirmed: block 101",
+            "Finalized: block 100",
+            "Finalized: tx 101:0",
+            "Finalized: block 101",
+            // finalization is announced in ascending slot order to confirmed and processed subscriptions
+            "Confirmed: Finalized 100",
+            "Processed: Finalized 100",
+            "Confirmed: Finalized 101",
+            "Processed: Finalized 101"
+        ]);
+    }
+}
\ No newline at end of file
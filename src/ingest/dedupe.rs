@@ -0,0 +1,475 @@
+use super::delivery::DeliveryMetrics;
+use super::source::{SourceMessage, SourceUpdate};
+use crate::data::{DataMessage, ItemIndex};
+use crate::geyser::api::SlotStatus;
+use futures_util::{stream, Stream, StreamExt};
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::time::Duration;
+use tokio::time::Instant;
+
+
+/// Number of recent slots, for which received updates are tracked
+pub const WINDOW_SLOTS: u64 = 16;
+
+
+/// Removes duplicate updates received from different sources
+/// and restores the slot order of the remaining ones.
+///
+/// Accepted updates are reported to the delivery metrics.
+pub fn dedupe(
+    input: impl Stream<Item = SourceMessage>,
+    deliveries: DeliveryMetrics
+) -> impl Stream<Item = SourceMessage>
+{
+    let mut dedupe = Dedupe::new(deliveries);
+    input.flat_map(move |msg| {
+        let mut out = Vec::new();
+        dedupe.push(msg, &mut out);
+        stream::iter(out)
+    })
+}
+
+
+/// Maximum time, for which updates of higher slots are held back,
+/// while nothing was received for the cursor slot
+const HOLD_TIMEOUT: Duration = Duration::from_millis(400);
+
+
+/// Tracks updates of the recent slots.
+///
+/// Updates of the lowest open slot (the cursor) pass through immediately,
+/// updates of higher slots are held back until all preceding slots are closed.
+/// A slot is closed, when its block is published, when it is dead,
+/// or when it falls out of the window.
+/// A slot without any updates is also closed, when the cluster is known to have skipped it
+/// or when updates of higher slots were held back for too long.
+/// Updates of closed slots, that were not received before, are dropped.
+struct Dedupe {
+    cursor: u64,
+    head: u64,
+    slots: BTreeMap<u64, SlotState>,
+    /// Known parents of the recent slots
+    parents: BTreeMap<u64, u64>,
+    /// Since when updates of higher slots are held back, while the cursor slot is empty
+    waiting_since: Option<Instant>,
+    deliveries: DeliveryMetrics
+}
+
+
+impl Dedupe {
+    fn new(deliveries: DeliveryMetrics) -> Self {
+        Self {
+            cursor: 0,
+            head: 0,
+            slots: BTreeMap::new(),
+            parents: BTreeMap::new(),
+            waiting_since: None,
+            deliveries
+        }
+    }
+
+    /// Processes the update and announces closed slots, when the cursor moves
+    fn push(&mut self, msg: SourceMessage, out: &mut Vec<SourceMessage>) {
+        let source = msg.source;
+        let cursor = self.cursor;
+        self.push_update(msg, out);
+        if self.cursor > cursor {
+            out.push(SourceMessage::new(source, SourceUpdate::SlotsClosed(self.cursor)));
+        }
+    }
+
+    fn push_update(&mut self, mut msg: SourceMessage, out: &mut Vec<SourceMessage>) {
+        let now = msg.received_at;
+        
+        let Some((slot, key)) = dedupe_key(&msg.update) else {
+            match &msg.update {
+                SourceUpdate::Slot(upd) => {
+                    if let Some(parent) = upd.parent {
+                        self.parents.insert(upd.slot, parent);
+                    }
+                    if upd.status() == SlotStatus::SlotDead {
+                        self.mark_dead(upd.slot, out);
+                    }
+                },
+                SourceUpdate::Data(DataMessage::Rollback(rollback)) => {
+                    for slot in rollback.slots.iter().copied() {
+                        self.mark_dead(slot, out);
+                    }
+                },
+                _ => {}
+            }
+            out.push(msg);
+            self.advance(now, out);
+            return
+        };
+
+        if let Some(parent) = block_parent(&msg.update) {
+            self.parents.insert(slot, parent);
+        }
+
+        if slot < self.cursor {
+            let is_duplicate = self.slots.get_mut(&slot).is_some_and(|state| !state.mark(key));
+            if !is_duplicate {
+                crate::metrics::register_dropped_update(msg.source);
+            }
+            return
+        }
+
+        if self.cursor == 0 {
+            // the very first update
+            self.cursor = slot;
+        }
+
+        let state = self.slots.entry(slot).or_insert_with(SlotState::new);
+        if !state.mark(key) {
+            return
+        }
+        if state.dead {
+            crate::metrics::register_dropped_update(msg.source);
+            return
+        }
+        self.deliveries.accept(&msg);
+        if slot < self.head {
+            crate::metrics::register_late_update(msg.source);
+        }
+
+        let is_block = matches!(key, DedupeKey::Block);
+        if slot == self.cursor {
+            self.waiting_since = None;
+            if is_block {
+                state.set_complete(&mut msg.update);
+                out.push(msg);
+                self.close_cursor(out);
+            } else {
+                out.push(msg);
+            }
+        } else if is_block {
+            state.block = Some(msg);
+        } else {
+            state.pending.push(msg);
+        }
+
+        self.head = self.head.max(slot);
+        while self.head.saturating_sub(self.cursor) > WINDOW_SLOTS {
+            if self.slots.contains_key(&self.cursor) {
+                self.close_cursor(out);
+            } else {
+                let next = self.slots.range(self.cursor..).next().map_or(u64::MAX, |(slot, _)| *slot);
+                self.skip_to(next.min(self.head - WINDOW_SLOTS), out);
+            }
+        }
+        self.advance(now, out);
+        self.evict();
+    }
+
+    fn mark_dead(&mut self, slot: u64, out: &mut Vec<SourceMessage>) {
+        if slot < self.cursor {
+            return
+        }
+        let state = self.slots.entry(slot).or_insert_with(SlotState::new);
+        state.dead = true;
+        state.pending.clear();
+        state.block = None;
+        if slot == self.cursor {
+            self.close_cursor(out);
+        }
+    }
+
+    fn close_cursor(&mut self, out: &mut Vec<SourceMessage>) {
+        self.skip_to(self.cursor + 1, out);
+    }
+
+    /// Moves the cursor to the given slot and releases held updates
+    fn skip_to(&mut self, slot: u64, out: &mut Vec<SourceMessage>) {
+        self.cursor = slot;
+        self.waiting_since = None;
+        while let Some(state) = self.slots.get_mut(&self.cursor) {
+            out.append(&mut state.pending);
+            if let Some(mut block) = state.block.take() {
+                state.set_complete(&mut block.update);
+                out.push(block);
+            } else if !state.dead {
+                return
+            }
+            self.cursor += 1;
+        }
+    }
+
+    /// Closes the empty cursor slot, when the cluster is known to have skipped it
+    /// or when updates of higher slots are held back for too long
+    fn advance(&mut self, now: Instant, out: &mut Vec<SourceMessage>) {
+        while self.cursor > 0 && !self.slots.contains_key(&self.cursor) {
+            let Some(next) = self.slots.range(self.cursor..).next().map(|(slot, _)| *slot) else {
+                self.waiting_since = None;
+                return
+            };
+            let waiting_since = *self.waiting_since.get_or_insert(now);
+            if self.is_skipped(self.cursor) {
+                self.skip_to(self.cursor + 1, out);
+            } else if now.saturating_duration_since(waiting_since) >= HOLD_TIMEOUT {
+                self.skip_to(next, out);
+            } else {
+                return
+            }
+        }
+    }
+
+    /// Whether the given slot lies between a known slot and its parent
+    fn is_skipped(&self, slot: u64) -> bool {
+        !self.parents.contains_key(&slot) && self.parents.range(slot + 1..).any(|(_, parent)| *parent < slot)
+    }
+
+    fn evict(&mut self) {
+        let bottom = self.head.saturating_sub(WINDOW_SLOTS).min(self.cursor);
+        while self.slots.first_key_value().is_some_and(|(slot, _)| *slot < bottom) {
+            self.slots.pop_first();
+        }
+        while self.parents.first_key_value().is_some_and(|(slot, _)| *slot < bottom) {
+            self.parents.pop_first();
+        }
+    }
+}
+
+
+struct SlotState {
+    transactions: Mask,
+    accounts: HashSet<u64>,
+    has_block: bool,
+    dead: bool,
+    /// Updates held back until the slot becomes the cursor
+    pending: Vec<SourceMessage>,
+    /// Block held back until the slot becomes the cursor
+    block: Option<SourceMessage>
+}
+
+
+impl SlotState {
+    fn new() -> Self {
+        Self {
+            transactions: Mask::new(5000),
+            accounts: HashSet::new(),
+            has_block: false,
+            dead: false,
+            pending: Vec::new(),
+            block: None
+        }
+    }
+
+    /// Returns `true` if the update was not received before
+    fn mark(&mut self, key: DedupeKey) -> bool {
+        match key {
+            DedupeKey::Block => !std::mem::replace(&mut self.has_block, true),
+            DedupeKey::Transaction(index) => self.transactions.mark(index),
+            DedupeKey::Account(key) => self.accounts.insert(key)
+        }
+    }
+
+    fn set_complete(&self, update: &mut SourceUpdate) {
+        if let SourceUpdate::Block(block) = update {
+            let received = self.transactions.count() as u64;
+            block.complete = block.transaction_count.map(|count| received >= count);
+        }
+    }
+}
+
+
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+pub enum DedupeKey {
+    Block,
+    Transaction(ItemIndex),
+    Account(u64)
+}
+
+
+fn block_parent(update: &SourceUpdate) -> Option<u64> {
+    match update {
+        SourceUpdate::Block(block) => Some(block.meta.parent_slot),
+        SourceUpdate::Data(DataMessage::Block(block)) => Some(block.parent_slot),
+        _ => None
+    }
+}
+
+
+pub fn dedupe_key(update: &SourceUpdate) -> Option<(u64, DedupeKey)> {
+    let key = match update {
+        SourceUpdate::Block(block) => (block.meta.slot, DedupeKey::Block),
+        SourceUpdate::Transaction(tx) => (tx.slot, DedupeKey::Transaction(tx.index)),
+        SourceUpdate::Account(acc) => (acc.slot, DedupeKey::Account(account_update_key(
+            &acc.account.pubkey,
+            acc.account.txn_signature.as_deref(),
+            acc.account.lamports,
+            &acc.account.data
+        ))),
+        SourceUpdate::Data(DataMessage::Block(block)) => (block.slot, DedupeKey::Block),
+        SourceUpdate::Data(DataMessage::Transaction(tx)) => {
+            (tx.slot, DedupeKey::Transaction(tx.transaction_index))
+        },
+        SourceUpdate::Data(DataMessage::Account(acc)) => {
+            let pubkey = bs58::decode(&acc.pubkey).into_vec().unwrap_or_default();
+            let txn_signature = acc.txn_signature.as_ref().map(|sig| {
+                bs58::decode(sig).into_vec().unwrap_or_default()
+            });
+            (acc.slot, DedupeKey::Account(account_update_key(
+                &pubkey,
+                txn_signature.as_deref(),
+                acc.lamports,
+                &acc.data
+            )))
+        },
+        SourceUpdate::Slot(_) | SourceUpdate::Data(_) | SourceUpdate::SlotsClosed(_) => return None
+    };
+    Some(key)
+}
+
+
+/// Identifies an account update regardless of the validator it came from
+fn account_update_key(pubkey: &[u8], txn_signature: Option<&[u8]>, lamports: u64, data: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    pubkey.hash(&mut hasher);
+    if let Some(signature) = txn_signature {
+        signature.hash(&mut hasher);
+    } else {
+        lamports.hash(&mut hasher);
+        data.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+
+struct Mask {
+    inner: Vec<bool>,
+    count: usize
+}
+
+
+impl Mask {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            inner: vec![false; capacity],
+            count: 0
+        }
+    }
+
+    pub fn mark(&mut self, i: usize) -> bool {
+        if i >= self.inner.len() {
+            let new_len = std::cmp::max(i + 1, self.inner.len() * 2);
+            self.inner.resize(new_len, false);
+        }
+        let is_set = &mut self.inner[i];
+        let set = !*is_set;
+        *is_set = true;
+        self.count += set as usize;
+        set
+    }
+
+    /// Number of marked positions
+    pub fn count(&self) -> usize {
+        self.count
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::testing::{block, slot, tx};
+
+    fn describe(msg: &SourceMessage) -> String {
+        match &msg.update {
+            SourceUpdate::Block(block) => format!("block {}", block.meta.slot),
+            SourceUpdate::Transaction(tx) => format!("tx {}:{}", tx.slot, tx.index),
+            SourceUpdate::Slot(upd) => format!("slot {}", upd.slot),
+            SourceUpdate::SlotsClosed(below) => format!("closed <{}", below),
+            upd => format!("{:?}", upd)
+        }
+    }
+
+    /// Feeds updates received at the given millisecond offsets and describes the output
+    fn run(updates: Vec<(u64, SourceUpdate)>) -> Vec<String> {
+        let start = Instant::now();
+        let mut dedupe = Dedupe::new(DeliveryMetrics::default());
+        let mut out = Vec::new();
+        for (ms, update) in updates {
+            let mut msg = SourceMessage::new("test", update);
+            msg.received_at = start + Duration::from_millis(ms);
+            dedupe.push(msg, &mut out);
+        }
+        out.iter().map(describe).collect()
+    }
+
+    #[test]
+    fn interleaved_slots() {
+        let out = run(vec![
+            (0, block(100, 99)),
+            (0, tx(102, 0)),
+            (0, tx(101, 0)),
+            (0, block(101, 100)),
+            (0, tx(102, 1)),
+        ]);
+        assert_eq!(out, vec![
+            "block 100", "closed <101", "tx 101:0", "block 101", "tx 102:0", "closed <102", "tx 102:1"
+        ]);
+    }
+
+    #[test]
+    fn skipped_slot() {
+        let out = run(vec![
+            (0, block(100, 99)),
+            (0, slot(102, 100, SlotStatus::SlotCreatedBank)),
+            (0, tx(102, 0)),
+        ]);
+        assert_eq!(out, vec!["block 100", "closed <101", "slot 102", "tx 102:0", "closed <102"]);
+    }
+
+    #[test]
+    fn hold_timeout() {
+        let out = run(vec![
+            (0, block(100, 99)),
+            (0, tx(102, 0)),
+            (300, tx(102, 1)),
+            (500, tx(102, 2)),
+            (600, tx(101, 0)),
+        ]);
+        assert_eq!(out, vec!["block 100", "closed <101", "tx 102:0", "tx 102:1", "tx 102:2", "closed <102"]);
+    }
+
+    #[test]
+    fn dead_slot() {
+        let out = run(vec![
+            (0, block(100, 99)),
+            (0, tx(101, 0)),
+            (0, tx(102, 0)),
+            (0, slot(101, 100, SlotStatus::SlotDead)),
+            (0, tx(101, 1)),
+            (0, tx(102, 1)),
+        ]);
+        assert_eq!(out, vec![
+            "block 100", "closed <101", "tx 101:0", "tx 102:0", "slot 101", "closed <102", "tx 102:1"
+        ]);
+    }
+
+    #[test]
+    fn window_overflow() {
+        let out = run(vec![
+            (0, block(100, 99)),
+            (0, tx(101, 0)),
+            (0, tx(102, 0)),
+            (0, tx(101 + WINDOW_SLOTS + 1, 0)),
+            (0, tx(101, 1)),
+            (0, tx(102, 1)),
+        ]);
+        assert_eq!(out, vec!["block 100", "closed <101", "tx 101:0", "tx 102:0", "closed <102", "tx 102:1"]);
+    }
+
+    #[test]
+    fn duplicates() {
+        let out = run(vec![
+            (0, tx(100, 0)),
+            (0, tx(100, 0)),
+            (0, block(100, 99)),
+            (0, block(100, 99)),
+            (0, tx(100, 0)),
+        ]);
+        assert_eq!(out, vec!["tx 100:0", "closed <100", "block 100", "closed <101"]);
+    }
+}
\ No newline at end of file
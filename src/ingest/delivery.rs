@@ -0,0 +1,126 @@
+use super::dedupe::{dedupe_key, DedupeKey, WINDOW_SLOTS};
+use super::source::SourceMessage;
+use crate::Name;
+use futures_util::{Stream, StreamExt};
+use std::collections::hash_map::Entry;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use tokio::time::Instant;
+
+
+/// Number of recent slots, for which deliveries are tracked
+const MAX_SLOTS: usize = 2 * WINDOW_SLOTS as usize;
+
+
+/// Counts transactions and blocks, that each source delivered first,
+/// and measures how far behind the winner other sources deliver them.
+///
+/// Receipts are registered for updates of all sources, i.e. before fallback and quorum,
+/// but the first delivery is only credited, when deduplication accepts the update.
+#[derive(Clone, Default)]
+pub struct DeliveryMetrics {
+    slots: Arc<Mutex<BTreeMap<u64, HashMap<DedupeKey, Delivery>>>>
+}
+
+
+enum Delivery {
+    /// Receipts by sources, while the item is not accepted yet
+    Pending(Vec<(Name, Instant)>),
+    /// The item was accepted from the given source
+    Accepted(Name, Instant)
+}
+
+
+impl DeliveryMetrics {
+    /// Registers receipts of the passing updates
+    pub fn observe(self, input: impl Stream<Item = SourceMessage>) -> impl Stream<Item = SourceMessage> {
+        input.inspect(move |msg| self.receive(msg))
+    }
+
+    fn receive(&self, msg: &SourceMessage) {
+        let Some((slot, key)) = item_key(msg) else {
+            return
+        };
+        let mut slots = self.slots.lock().unwrap();
+        if slots.len() >= MAX_SLOTS && slots.first_key_value().is_some_and(|(first, _)| slot < *first) {
+            // the item was already forgotten
+            return
+        }
+        match slots.entry(slot).or_default().entry(key) {
+            Entry::Vacant(entry) => {
+                entry.insert(Delivery::Pending(vec![(msg.source, msg.received_at)]));
+            },
+            Entry::Occupied(mut entry) => match entry.get_mut() {
+                Delivery::Pending(receipts) => {
+                    if !receipts.iter().any(|(source, _)| *source == msg.source) {
+                        receipts.push((msg.source, msg.received_at));
+                    }
+                },
+                Delivery::Accepted(winner, accepted_at) => {
+                    if *winner != msg.source {
+                        let lag = msg.received_at.saturating_duration_since(*accepted_at);
+                        crate::metrics::register_delivery_lag(msg.source, lag);
+                    }
+                }
+            }
+        }
+        while slots.len() > MAX_SLOTS {
+            slots.pop_first();
+        }
+    }
+
+    /// Credits the first delivery of an accepted update to its source
+    /// and measures the lag of the sources, that delivered the item before
+    pub fn accept(&self, msg: &SourceMessage) {
+        let Some((slot, key)) = item_key(msg) else {
+            return
+        };
+        crate::metrics::register_first_delivery(msg.source);
+        let mut slots = self.slots.lock().unwrap();
+        let Some(delivery) = slots.get_mut(&slot).and_then(|items| items.get_mut(&key)) else {
+            return
+        };
+        let accepted = Delivery::Accepted(msg.source, msg.received_at);
+        if let Delivery::Pending(receipts) = std::mem::replace(delivery, accepted) {
+            for (source, received_at) in receipts {
+                if source != msg.source {
+                    let lag = received_at.saturating_duration_since(msg.received_at);
+                    crate::metrics::register_delivery_lag(source, lag);
+                }
+            }
+        }
+    }
+}
+
+
+fn item_key(msg: &SourceMessage) -> Option<(u64, DedupeKey)> {
+    dedupe_key(&msg.update).filter(|(_, key)| !matches!(key, DedupeKey::Account(_)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ingest::testing::tx;
+    use std::time::Duration;
+
+    #[test]
+    fn credit_accepted_source() {
+        let metrics = DeliveryMetrics::default();
+        let start = Instant::now();
+        let receive = |source, ms| {
+            let mut msg = SourceMessage::new(source, tx(10, 0));
+            msg.received_at = start + Duration::from_millis(ms);
+            metrics.receive(&msg);
+            msg
+        };
+        // the earlier update of `a` was not accepted, e.g. because it didn't reach the quorum
+        receive("a", 0);
+        let accepted = receive("b", 10);
+        metrics.accept(&accepted);
+        receive("c", 20);
+
+        let slots = metrics.slots.lock().unwrap();
+        let delivery = &slots[&10][&DedupeKey::Transaction(0)];
+        assert!(matches!(delivery, Delivery::Accepted("b", at) if *at == accepted.received_at));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,212 @@
+use super::dedupe::dedupe_key;
+use super::source::{SourceMessage, SourceUpdate};
+use crate::Name;
+use futures_util::{stream, Stream, StreamExt};
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::watch;
+use tokio::time::Instant;
+use tracing::info;
+
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SourceRole {
+    /// Updates are always used
+    #[default]
+    Primary,
+    /// Updates are used only while primary sources are lagging or down
+    Backup,
+    /// Same as [SourceRole::Backup], but connected only while the fallback is active
+    LazyBackup
+}
+
+
+#[derive(Debug, Copy, Clone)]
+pub struct FallbackOptions {
+    /// Number of slots, by which primary sources may lag behind backups
+    pub lag_slots: u64,
+    /// Maximum time without primary updates
+    pub timeout: Duration
+}
+
+
+impl Default for FallbackOptions {
+    fn default() -> Self {
+        Self {
+            lag_slots: 4,
+            timeout: Duration::from_secs(5)
+        }
+    }
+}
+
+
+/// Tracks progress of primary and backup sources
+/// and decides, whether updates of backup sources should be used.
+///
+/// The fallback is activated, when primary sources lag behind backups by more than
+/// the configured number of slots or don't deliver any updates for the configured time.
+/// It is deactivated, once primary sources catch up.
+#[derive(Clone)]
+pub struct Fallback {
+    inner: Arc<Inner>
+}
+
+
+struct Inner {
+    options: FallbackOptions,
+    backups: HashSet<Name>,
+    state: Mutex<State>,
+    active: watch::Sender<bool>
+}
+
+
+struct State {
+    primary_head: u64,
+    backup_head: u64,
+    last_primary_update: Instant
+}
+
+
+impl Fallback {
+    pub fn new(options: FallbackOptions, backups: HashSet<Name>) -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                options,
+                backups,
+                state: Mutex::new(State {
+                    primary_head: 0,
+                    backup_head: 0,
+                    last_primary_update: Instant::now()
+                }),
+                active: watch::Sender::new(false)
+            })
+        }
+    }
+
+    /// Returns a receiver, that tells whether the fallback is active
+    pub fn subscribe(&self) -> watch::Receiver<bool> {
+        self.inner.active.subscribe()
+    }
+
+    fn is_active(&self) -> bool {
+        *self.inner.active.borrow()
+    }
+
+    fn primary_head(&self) -> u64 {
+        self.inner.state.lock().unwrap().primary_head
+    }
+
+    /// Registers an update of the given slot and returns `true` if it should be used
+    fn accept(&self, source: Name, slot: Option<u64>) -> bool {
+        let mut state = self.inner.state.lock().unwrap();
+        let is_backup = self.inner.backups.contains(source);
+        if is_backup {
+            if let Some(slot) = slot {
+                state.backup_head = state.backup_head.max(slot);
+            }
+        } else {
+            if let Some(slot) = slot {
+                state.primary_head = state.primary_head.max(slot);
+            }
+            state.last_primary_update = Instant::now();
+        }
+
+        let active = self.is_active();
+        if active && !is_backup && state.primary_head >= state.backup_head {
+            self.set_active(false, &state);
+        } else if !active && state.backup_head > state.primary_head + self.inner.options.lag_slots {
+            self.set_active(true, &state);
+        }
+
+        !is_backup || self.is_active()
+    }
+
+    /// Activates the fallback, when primary sources stop delivering updates
+    pub async fn watchdog(self) {
+        let period = (self.inner.options.timeout / 10).max(Duration::from_millis(10));
+        let mut interval = tokio::time::interval(period);
+        loop {
+            interval.tick().await;
+            let state = self.inner.state.lock().unwrap();
+            if !self.is_active() && state.last_primary_update.elapsed() > self.inner.options.timeout {
+                self.set_active(true, &state);
+            }
+        }
+    }
+
+    fn set_active(&self, active: bool, state: &State) {
+        if active {
+            info!(
+                primary_head = state.primary_head,
+                backup_head = state.backup_head,
+                "primary sources are lagging or down, switching to backups"
+            );
+        } else {
+            info!(
+                primary_head = state.primary_head,
+                backup_head = state.backup_head,
+                "primary sources caught up, ignoring backups"
+            );
+        }
+        crate::metrics::set_fallback_active(active);
+        self.inner.active.send_replace(active);
+    }
+}
+
+
+/// Drops updates of backup sources, while the fallback is not active.
+///
+/// Backup updates of slots, that primary sources haven't reached yet, are held back
+/// and released on activation, so that switching doesn't leave a gap.
+pub fn fallback(
+    input: impl Stream<Item = SourceMessage>,
+    fallback: Option<Fallback>
+) -> impl Stream<Item = SourceMessage>
+{
+    let mut held: Vec<(u64, SourceMessage)> = Vec::new();
+    input.flat_map(move |msg| {
+        let mut out = Vec::new();
+        match fallback.as_ref() {
+            Some(fallback) => {
+                let slot = update_slot(&msg.update);
+                if fallback.accept(msg.source, slot) {
+                    if fallback.is_active() {
+                        out.extend(held.drain(..).map(|(_, msg)| msg));
+                    }
+                    out.push(msg);
+                } else {
+                    let primary_head = fallback.primary_head();
+                    held.retain(|(slot, _)| *slot > primary_head);
+                    if let Some(slot) = slot.filter(|slot| *slot > primary_head) {
+                        held.push((slot, msg));
+                    }
+                }
+            },
+            None => out.push(msg)
+        }
+        stream::iter(out)
+    })
+}
+
+
+fn update_slot(update: &SourceUpdate) -> Option<u64> {
+    match update {
+        SourceUpdate::Slot(upd) => Some(upd.slot),
+        update => dedupe_key(update).map(|(slot, _)| slot)
+    }
+}
+
+
+/// Completes, when the fallback is deactivated.
+///
+/// Never completes without a receiver.
+pub async fn fallback_deactivated(active: Option<&mut watch::Receiver<bool>>) {
+    let deactivated = match active {
+        Some(active) => active.wait_for(|active| !*active).await.is_ok(),
+        None => false
+    };
+    if !deactivated {
+        std::future::pending().await
+    }
+}
\ No newline at end of file
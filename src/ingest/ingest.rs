@@ -1,8 +1,15 @@
-use super::processing::{processing_loop, Broadcast};
-use super::source::{source_loop, SourceMessage};
+use super::broadcast::Broadcast;
+use super::fallback::{Fallback, FallbackOptions, SourceRole};
+use super::processing::processing_loop;
+use super::pushdown::FilterPushdown;
+use super::replay::{replay_loop, ReplayOptions};
+use super::rpc::{rpc_source_loop, RpcSourceOptions};
+use super::source::{source_loop, SourceMessage, SourceOptions};
+use super::spray::{spray_source_loop, SprayOptions};
 use crate::geyser::GeyserClient;
 use crate::Name;
 use anyhow::anyhow;
+use std::collections::HashSet;
 use std::pin::{pin, Pin};
 use std::task::{Context, Poll};
 use tokio::task::JoinHandle;
@@ -10,42 +17,107 @@ use tokio_stream::wrappers::ReceiverStream;
 
 
 pub struct Ingest {
-    sources: Vec<(Name, GeyserClient)>
+    sources: Vec<(Name, Source)>,
+    quorum: Option<usize>,
+    fallback: FallbackOptions
+}
+
+
+enum Source {
+    Geyser(GeyserClient, SourceOptions),
+    Replay(ReplayOptions),
+    Rpc(RpcSourceOptions),
+    Spray(SprayOptions)
 }
 
 
 impl Ingest {
     pub fn new() -> Self {
         Self {
-            sources: Vec::new()
+            sources: Vec::new(),
+            quorum: None,
+            fallback: FallbackOptions::default()
         }
     }
     
-    pub fn add_source(&mut self, name: Name, client: GeyserClient) {
-        self.sources.push((name, client))
+    /// Publish data only after it was received from the given number of sources
+    pub fn set_quorum(&mut self, quorum: usize) {
+        self.quorum = Some(quorum)
+    }
+    
+    /// Set conditions, under which updates of backup sources are used
+    pub fn set_fallback(&mut self, options: FallbackOptions) {
+        self.fallback = options
     }
     
-    pub fn start(self, broadcast: Broadcast) -> IngestHandle {
+    pub fn add_source(&mut self, name: Name, client: GeyserClient, options: SourceOptions) {
+        self.sources.push((name, Source::Geyser(client, options)))
+    }
+    
+    pub fn add_replay_source(&mut self, name: Name, options: ReplayOptions) {
+        self.sources.push((name, Source::Replay(options)))
+    }
+    
+    pub fn add_rpc_source(&mut self, name: Name, options: RpcSourceOptions) {
+        self.sources.push((name, Source::Rpc(options)))
+    }
+    
+    pub fn add_spray_source(&mut self, name: Name, options: SprayOptions) {
+        self.sources.push((name, Source::Spray(options)))
+    }
+    
+    pub fn start(self, broadcast: Broadcast, pushdown: FilterPushdown) -> IngestHandle {
         let (source_tx, source_rx) = tokio::sync::mpsc::channel::<SourceMessage>(20_000);
 
-        let sources: Vec<_> = self.sources.into_iter().map(|(name, client)| {
-            let task = tokio::spawn(
-                source_loop(source_tx.clone(), name, client)
-            );
+        let backups: HashSet<Name> = self.sources.iter().filter_map(|(name, source)| {
+            match source {
+                Source::Geyser(_, options) if options.role != SourceRole::Primary => Some(*name),
+                _ => None
+            }
+        }).collect();
+        
+        let fallback = (!backups.is_empty()).then(|| Fallback::new(self.fallback, backups));
+
+        let sources: Vec<_> = self.sources.into_iter().map(|(name, source)| {
+            let task = match source {
+                Source::Geyser(client, options) => {
+                    let standby = match (options.role, fallback.as_ref()) {
+                        (SourceRole::LazyBackup, Some(fallback)) => Some(fallback.subscribe()),
+                        _ => None
+                    };
+                    tokio::spawn(
+                        source_loop(source_tx.clone(), name, client, options, pushdown.subscribe(), standby)
+                    )
+                },
+                Source::Replay(options) => tokio::spawn(
+                    replay_loop(source_tx.clone(), name, options)
+                ),
+                Source::Rpc(options) => tokio::spawn(
+                    rpc_source_loop(source_tx.clone(), name, options)
+                ),
+                Source::Spray(options) => tokio::spawn(
+                    spray_source_loop(source_tx.clone(), name, options)
+                )
+            };
             (name, false, task)
         }).collect();
 
         let processing = tokio::spawn(
             processing_loop(
                 broadcast,
-                ReceiverStream::new(source_rx)
+                ReceiverStream::new(source_rx),
+                self.quorum,
+                fallback.clone()
             )
         );
         
+        let watchdog = fallback.map(|fallback| tokio::spawn(fallback.watchdog()));
+        
         IngestHandle {
             terminated: false,
             sources,
-            processing
+            processing,
+            watchdog
         }
     }
 }
@@ -54,7 +126,8 @@ impl Ingest {
 pub struct IngestHandle {
     terminated: bool,
     sources: Vec<(Name, bool, JoinHandle<anyhow::Result<()>>)>,
-    processing: JoinHandle<()>
+    processing: JoinHandle<()>,
+    watchdog: Option<JoinHandle<()>>
 }
 
 
@@ -122,6 +195,9 @@ impl IngestHandle {
         }
         self.terminated = true;
         self.processing.abort();
+        if let Some(watchdog) = self.watchdog.as_ref() {
+            watchdog.abort();
+        }
         for (_, _, handle) in self.sources.iter() {
             handle.abort();
         }
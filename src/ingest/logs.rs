@@ -0,0 +1,288 @@
+use crate::data::{Instruction, ItemIndex, LogKind, LogMessage};
+
+
+const TRUNCATION_MARKER: &str = "Log truncated";
+
+
+/// Parses transaction log messages into log items
+/// and attributes them (as well as consumed compute units) to instructions through the invoke stack.
+///
+/// Returns `true` as a second element of the result when some of the logs were dropped.
+pub fn parse_logs(messages: Vec<String>, instructions: &mut [Instruction]) -> (Vec<LogMessage>, bool) {
+    let mut parser = LogParser {
+        instructions,
+        stack: Vec::with_capacity(5),
+        next: 0,
+        logs: Vec::new()
+    };
+
+    for msg in messages {
+        if !parser.parse_message(msg) {
+            parser.mark_dropped();
+            return (parser.logs, true)
+        }
+    }
+
+    (parser.logs, false)
+}
+
+
+struct LogParser<'a> {
+    instructions: &'a mut [Instruction],
+    stack: Vec<ItemIndex>,
+    next: ItemIndex,
+    logs: Vec<LogMessage>
+}
+
+
+impl<'a> LogParser<'a> {
+    fn parse_message(&mut self, msg: String) -> bool {
+        if msg == TRUNCATION_MARKER {
+            return false
+        }
+
+        if let Some(text) = msg.strip_prefix("Program log: ") {
+            self.push_log(LogKind::Log, text.to_string());
+            return true
+        }
+
+        if let Some(data) = msg.strip_prefix("Program data: ") {
+            self.push_log(LogKind::Data, data.to_string());
+            return true
+        }
+
+        if let Some((program, action)) = msg.strip_prefix("Program ").and_then(|s| s.split_once(' ')) {
+            if let Some(depth) = action.strip_prefix("invoke [").and_then(|s| s.strip_suffix(']')) {
+                return depth.parse().is_ok_and(|depth| self.invoke(program, depth))
+            }
+            if action == "success" || action.starts_with("failed: ") {
+                return self.exit(program)
+            }
+            if let Some((cu, _)) = action.strip_prefix("consumed ").and_then(|s| s.split_once(' ')) {
+                return cu.parse().is_ok_and(|cu| self.consume(program, cu))
+            }
+        }
+
+        self.push_log(LogKind::Other, msg);
+        true
+    }
+
+    fn invoke(&mut self, program: &str, depth: usize) -> bool {
+        if depth == 1 {
+            if !self.stack.is_empty() {
+                return false
+            }
+            // precompiled programs are executed without invoke logs
+            let skipped = self.instructions[self.next..].iter().position(|ins| {
+                ins.instruction_address.len() == 1 && program_id(ins) == program
+            });
+            let Some(skipped) = skipped else {
+                return false
+            };
+            self.next += skipped;
+        } else if self.stack.len() + 1 != depth {
+            return false
+        }
+
+        let Some(ins) = self.instructions.get(self.next) else {
+            return false
+        };
+
+        if ins.instruction_address.len() != depth || program_id(ins) != program {
+            return false
+        }
+
+        self.stack.push(self.next);
+        self.next += 1;
+        true
+    }
+
+    fn exit(&mut self, program: &str) -> bool {
+        match self.stack.pop() {
+            Some(i) => program_id(&self.instructions[i]) == program,
+            None => false
+        }
+    }
+
+    fn consume(&mut self, program: &str, compute_units: u64) -> bool {
+        let Some(i) = self.stack.last().copied() else {
+            return false
+        };
+        let ins = &mut self.instructions[i];
+        if program_id(ins) != program {
+            return false
+        }
+        ins.compute_units_consumed = Some(compute_units);
+        true
+    }
+
+    fn push_log(&mut self, kind: LogKind, message: String) {
+        let Some(instruction_index) = self.stack.last().copied() else {
+            return
+        };
+        let ins = &self.instructions[instruction_index];
+        self.logs.push(LogMessage {
+            log_index: self.logs.len(),
+            instruction_index,
+            instruction_address: ins.instruction_address.clone(),
+            program_id: program_id(ins).to_string(),
+            kind,
+            message
+        })
+    }
+
+    /// Marks the current and all subsequent instructions as having dropped logs
+    fn mark_dropped(&mut self) {
+        for i in self.stack.iter().copied() {
+            self.instructions[i].has_dropped_log_messages = true;
+        }
+        for ins in self.instructions[self.next..].iter_mut() {
+            ins.has_dropped_log_messages = true;
+        }
+    }
+}
+
+
+fn program_id(ins: &Instruction) -> &str {
+    &ins.account_list[ins.program_id as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::AccountIndex;
+
+    const ACCOUNTS: [&str; 3] = ["A", "B", "C"];
+
+    fn ins(instruction_address: &[ItemIndex], program: &str) -> Instruction {
+        Instruction {
+            instruction_address: instruction_address.to_vec(),
+            program_id: ACCOUNTS.iter().position(|a| *a == program).unwrap() as AccountIndex,
+            accounts: Vec::new(),
+            data: String::new(),
+            binary_data: Vec::new(),
+            error: None,
+            compute_units_consumed: None,
+            is_committed: true,
+            has_dropped_log_messages: false,
+            account_list: ACCOUNTS.iter().map(|a| a.to_string()).collect::<Vec<_>>().into()
+        }
+    }
+
+    fn parse(messages: &[&str], instructions: &mut [Instruction]) -> (Vec<String>, bool) {
+        let messages = messages.iter().map(|m| m.to_string()).collect();
+        let (logs, dropped) = parse_logs(messages, instructions);
+        let logs = logs.iter().map(|log| {
+            format!("{} {:?} {:?} {}", log.instruction_index, log.instruction_address, log.kind, log.message)
+        }).collect();
+        (logs, dropped)
+    }
+
+    #[test]
+    fn nested_invokes() {
+        let mut instructions = vec![ins(&[0], "A"), ins(&[0, 0], "B"), ins(&[0, 0, 0], "C"), ins(&[1], "B")];
+        let (logs, dropped) = parse(&[
+            "Program A invoke [1]",
+            "Program log: a",
+            "Program B invoke [2]",
+            "Program C invoke [3]",
+            "Program log: c",
+            "Program C success",
+            "Program log: b",
+            "Program B success",
+            "Program A success",
+            "Program B invoke [1]",
+            "Program log: b1",
+            "Program B success",
+        ], &mut instructions);
+        assert!(!dropped);
+        assert_eq!(logs, vec![
+            "0 [0] Log a",
+            "2 [0, 0, 0] Log c",
+            "1 [0, 0] Log b",
+            "3 [1] Log b1",
+        ]);
+    }
+
+    #[test]
+    fn log_kinds() {
+        let mut instructions = vec![ins(&[0], "A")];
+        let (logs, dropped) = parse(&[
+            "Program A invoke [1]",
+            "Program log: hello",
+            "Program data: AQID",
+            "Program A consumed 10 of 200000 compute units",
+            "Program return: A AQ==",
+            "Program A success",
+        ], &mut instructions);
+        assert!(!dropped);
+        assert_eq!(logs, vec![
+            "0 [0] Log hello",
+            "0 [0] Data AQID",
+            "0 [0] Other Program return: A AQ==",
+        ]);
+    }
+
+    #[test]
+    fn failed_inner_invoke() {
+        let mut instructions = vec![ins(&[0], "A"), ins(&[0, 0], "B")];
+        let (logs, dropped) = parse(&[
+            "Program A invoke [1]",
+            "Program B invoke [2]",
+            "Program log: oops",
+            "Program B consumed 100 of 199000 compute units",
+            "Program B failed: custom program error: 0x1",
+            "Program A consumed 1000 of 200000 compute units",
+            "Program A failed: custom program error: 0x1",
+        ], &mut instructions);
+        assert!(!dropped);
+        assert_eq!(logs, vec!["1 [0, 0] Log oops"]);
+        assert_eq!(instructions[0].compute_units_consumed, Some(1000));
+        assert_eq!(instructions[1].compute_units_consumed, Some(100));
+    }
+
+    #[test]
+    fn truncated_logs() {
+        let mut instructions = vec![ins(&[0], "A"), ins(&[0, 0], "B"), ins(&[1], "C")];
+        let (logs, dropped) = parse(&[
+            "Program A invoke [1]",
+            "Program log: a",
+            "Program B invoke [2]",
+            "Program B success",
+            "Log truncated",
+        ], &mut instructions);
+        assert!(dropped);
+        assert_eq!(logs, vec!["0 [0] Log a"]);
+        let flags: Vec<bool> = instructions.iter().map(|ins| ins.has_dropped_log_messages).collect();
+        assert_eq!(flags, vec![true, false, true]);
+    }
+
+    #[test]
+    fn consumed_units() {
+        let mut instructions = vec![ins(&[0], "A"), ins(&[0, 0], "B"), ins(&[1], "C")];
+        let (_, dropped) = parse(&[
+            "Program A invoke [1]",
+            "Program B invoke [2]",
+            "Program B consumed 300 of 199500 compute units",
+            "Program B success",
+            "Program A consumed 800 of 200000 compute units",
+            "Program A success",
+            "Program C invoke [1]",
+            "Program C success",
+        ], &mut instructions);
+        assert!(!dropped);
+        let units: Vec<Option<u64>> = instructions.iter().map(|ins| ins.compute_units_consumed).collect();
+        assert_eq!(units, vec![Some(800), Some(300), None]);
+    }
+
+    #[test]
+    fn unexpected_invoke() {
+        let mut instructions = vec![ins(&[0], "A")];
+        let (_, dropped) = parse(&[
+            "Program B invoke [1]",
+            "Program B success",
+        ], &mut instructions);
+        assert!(dropped);
+        assert!(instructions[0].has_dropped_log_messages);
+    }
+}
\ No newline at end of file
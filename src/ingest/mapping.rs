@@ -1,6 +1,7 @@
-use super::source::TransactionUpdate;
-use crate::data::{AccountList, Balance, Instruction, JsonString, TokenBalance, Transaction, TransactionData, TransactionVersion};
-use crate::geyser::solana::storage::confirmed_block::MessageAddressTableLookup;
+use super::logs::parse_logs;
+use super::source::{AccountUpdate, BlockUpdate, TransactionUpdate};
+use crate::data::{AccountData, AccountList, Balance, BlockData, Instruction, JsonString, Reward, RewardType, TokenBalance, Transaction, TransactionData, TransactionVersion};
+use crate::geyser::solana::storage::confirmed_block::{self, MessageAddressTableLookup};
 use crate::json_builder::{safe_prop, JsonBuilder};
 use anyhow::{anyhow, ensure, Context};
 use solana_transaction_error::TransactionError;
@@ -14,6 +15,57 @@ macro_rules! conv {
 }
 
 
+pub fn map_block(update: BlockUpdate) -> BlockData {
+    let block = update.meta;
+    BlockData {
+        slot: block.slot,
+        hash: block.blockhash,
+        parent_slot: block.parent_slot,
+        parent_hash: block.parent_blockhash,
+        height: block.block_height.map(|h| h.block_height),
+        timestamp: block.block_time.map_or(0, |t| t.timestamp),
+        rewards: block.rewards.map_or_else(Vec::new, |r| map_rewards(r.rewards)),
+        complete: update.complete
+    }
+}
+
+
+fn map_rewards(rewards: Vec<confirmed_block::Reward>) -> Vec<Reward> {
+    rewards.into_iter().map(|r| {
+        let reward_type = match r.reward_type() {
+            confirmed_block::RewardType::Unspecified => None,
+            confirmed_block::RewardType::Fee => Some(RewardType::Fee),
+            confirmed_block::RewardType::Rent => Some(RewardType::Rent),
+            confirmed_block::RewardType::Staking => Some(RewardType::Staking),
+            confirmed_block::RewardType::Voting => Some(RewardType::Voting)
+        };
+        Reward {
+            pubkey: r.pubkey,
+            lamports: r.lamports,
+            post_balance: r.post_balance,
+            reward_type,
+            commission: r.commission.parse().ok()
+        }
+    }).collect()
+}
+
+
+pub fn map_account(update: AccountUpdate) -> AccountData {
+    let account = update.account;
+    AccountData {
+        slot: update.slot,
+        pubkey: bs58::encode(&account.pubkey).into_string(),
+        lamports: account.lamports,
+        owner: bs58::encode(&account.owner).into_string(),
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: account.data,
+        write_version: account.write_version,
+        txn_signature: account.txn_signature.map(|sig| bs58::encode(sig).into_string())
+    }
+}
+
+
 pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionData> {
     let meta = update.meta;
 
@@ -39,7 +91,8 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
         }
     });
     
-    let transaction = Transaction {
+    let mut transaction = Transaction {
+        is_vote: update.is_vote,
         version: if update.versioned { TransactionVersion::Legacy } else { TransactionVersion::Other(0) },
         account_keys: update.account_keys.len(),
         address_table_lookups: render_address_table_lookups(&update.address_table_lookups),
@@ -48,6 +101,7 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
         num_required_signatures: conv!(u8, update.header.num_required_signatures)?,
         recent_blockhash: bs58::encode(&update.recent_blockhash).into_string(),
         signatures: JsonBuilder::render(|json| json.base58_list(&update.signatures)),
+        signature: update.signatures.first().map(|sig| bs58::encode(sig).into_string()),
         err: transaction_error,
         compute_units_consumed: meta.compute_units_consumed,
         fee: meta.fee,
@@ -57,6 +111,7 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
             safe_prop!(json, "readonly", json.base58_list(&meta.loaded_readonly_addresses));
             json.end_object();
         }),
+        has_dropped_log_messages: meta.log_messages_none,
     };
 
     let accounts: AccountList = {
@@ -68,7 +123,7 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
         accounts.into()
     };
 
-    let instructions = {
+    let mut instructions = {
         let len = update.instructions.len() + meta.inner_instructions.iter().map(|l| l.instructions.len()).sum::<usize>();
         let mut instructions: Vec<Instruction> = Vec::with_capacity(len);
         let mut address = Vec::with_capacity(5);
@@ -99,8 +154,10 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
                         data: bs58::encode(&$ins.data).into_string(),
                         binary_data: $ins.data,
                         error: None,
+                        compute_units_consumed: None,
                         account_list: accounts.clone(),
                         is_committed: transaction.err.is_none(),
+                        has_dropped_log_messages: meta.log_messages_none,
                     });
                 }};
             }
@@ -142,6 +199,14 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
         instructions
     };
 
+    let logs = if meta.log_messages_none {
+        Vec::new()
+    } else {
+        let (logs, dropped) = parse_logs(meta.log_messages, &mut instructions);
+        transaction.has_dropped_log_messages |= dropped;
+        logs
+    };
+
     let balances = {
         ensure!(meta.pre_balances.len() == meta.post_balances.len());
         ensure!(accounts.len() >= meta.pre_balances.len());
@@ -204,6 +269,7 @@ pub fn map_transaction(update: TransactionUpdate) -> anyhow::Result<TransactionD
         instructions,
         balances,
         token_balances,
+        logs,
         accounts
     })
 }
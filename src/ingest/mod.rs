@@ -1,8 +1,30 @@
+mod broadcast;
+mod buffer;
+mod commitment;
+mod dedupe;
+mod delivery;
+mod fallback;
 mod ingest;
+mod logs;
 mod mapping;
 mod processing;
+mod pushdown;
+mod quorum;
+mod recorder;
+mod replay;
+mod rpc;
 mod source;
+mod spray;
+#[cfg(test)]
+pub mod testing;
 
 
 pub use ingest::*;
-pub use processing::Broadcast;
\ No newline at end of file
+pub use broadcast::Broadcast;
+pub use buffer::BufferOptions;
+pub use fallback::{FallbackOptions, SourceRole};
+pub use pushdown::FilterPushdown;
+pub use replay::{read_recording, ReplayClock, ReplayOptions};
+pub use rpc::RpcSourceOptions;
+pub use source::{SourceMode, SourceOptions};
+pub use spray::SprayOptions;
\ No newline at end of file
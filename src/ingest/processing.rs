@@ -1,35 +1,58 @@
-use super::mapping::map_transaction;
+use super::broadcast::Broadcast;
+use super::commitment::CommitmentTracker;
+use super::dedupe::dedupe;
+use super::delivery::DeliveryMetrics;
+use super::fallback::{fallback as apply_fallback, Fallback};
+use super::mapping::{map_account, map_block, map_transaction};
+use super::quorum::quorum as apply_quorum;
 use super::source::{SourceMessage, SourceUpdate};
-use crate::data::{BlockData, DataMessage};
+use crate::data::{Commitment, DataMessage};
+use crate::geyser::api::SlotStatus;
 use std::pin::pin;
 use std::sync::Arc;
 use tokio_stream::{Stream, StreamExt};
 use tracing::{debug, error};
 
 
-pub type Broadcast = tokio::sync::broadcast::Sender<Arc<DataMessage>>;
-
-
 pub async fn processing_loop(
     broadcast: Broadcast,
-    input: impl Stream<Item = SourceMessage>
+    input: impl Stream<Item = SourceMessage>,
+    quorum: Option<usize>,
+    fallback: Option<Fallback>
 ) {
-    let input = dedupe(input);
+    let deliveries = DeliveryMetrics::default();
+    let input = deliveries.clone().observe(input);
+    let input = dedupe(apply_quorum(apply_fallback(input, fallback), quorum), deliveries);
     let mut input = pin!(input);
+    let mut commitment = CommitmentTracker::new();
+    let mut emit = |level, msg| broadcast.send(level, msg);
     while let Some(msg) = input.next().await {
         let data_msg = match msg.update {
-            SourceUpdate::Block(block) => {
-                let block = BlockData {
-                    slot: block.slot,
-                    hash: block.blockhash,
-                    parent_slot: block.parent_slot,
-                    parent_hash: block.parent_blockhash,
-                    height: block.block_height.map(|h| h.block_height),
-                    timestamp: block.block_time.map_or(0, |t| t.timestamp)
+            SourceUpdate::Slot(upd) => {
+                let status = match upd.status() {
+                    SlotStatus::SlotConfirmed => Commitment::Confirmed,
+                    SlotStatus::SlotFinalized => Commitment::Finalized,
+                    SlotStatus::SlotDead => {
+                        debug!(
+                            slot = upd.slot,
+                            dead_error = upd.dead_error,
+                            source = msg.source,
+                            "slot is dead"
+                        );
+                        commitment.mark_dead(upd.slot, &mut emit);
+                        continue
+                    },
+                    _ => Commitment::Processed
                 };
+                commitment.update_status(upd.slot, upd.parent, status, &mut emit);
+                continue
+            },
+            SourceUpdate::Block(block) => {
+                let block = map_block(block);
                 debug!(
                     slot = block.slot,
                     block_time =% chrono::DateTime::from_timestamp(block.timestamp, 0).unwrap(),
+                    complete = block.complete,
                     source = msg.source,
                     "published"
                 );
@@ -66,71 +89,66 @@ pub async fn processing_loop(
                         continue
                     }
                 }
-            }
-        };
-        let _ = broadcast.send(Arc::new(data_msg));
-    }
-}
-
-
-fn dedupe(input: impl Stream<Item = SourceMessage>) -> impl Stream<Item = SourceMessage> {
-    let mut slot = 0;
-    let mut received_transactions = Mask::new(5000);
-    input.filter_map(move |msg| {
-        match &msg.update {
-            SourceUpdate::Block(block) => {
-                if block.slot >= slot {
-                    slot = block.slot + 1;
-                    received_transactions.reset();
-                    Some(msg)
-                } else {
-                    None
-                }
             },
-            SourceUpdate::Transaction(tx) => {
-                if tx.slot > slot {
-                    slot = tx.slot;
-                    received_transactions.reset();
-                }
-                if tx.slot == slot && received_transactions.mark(tx.index as usize) {
-                    Some(msg)
-                } else {
-                    None
-                }
-            }
-        }
-    })
-}
-
-
-struct Mask {
-    inner: Vec<bool>
-}
-
-
-impl Mask {
-    pub fn new(capacity: usize) -> Self {
-        Self {
-            inner: vec![false; capacity]
-        }
-    }
-
-    pub fn mark(&mut self, i: usize) -> bool {
-        match self.inner.get_mut(i) {
-            Some(is_set) => {
-                let set = !*is_set;
-                *is_set = true;
-                set
+            SourceUpdate::Account(acc) => {
+                let acc = map_account(acc);
+                debug!(
+                    slot = acc.slot,
+                    write_version = acc.write_version,
+                    source = msg.source,
+                    "published"
+                );
+                crate::metrics::register_account_publication(msg.source);
+                DataMessage::Account(acc)
             },
-            None => {
-                let new_len = std::cmp::max(i, self.inner.len() * 2);
-                self.inner.resize(new_len, false);
-                true
+            SourceUpdate::SlotsClosed(below) => {
+                commitment.close_slots(below, &mut emit);
+                continue
             },
-        }
-    }
-
-    pub fn reset(&mut self) {
-        self.inner.fill(false)
+            SourceUpdate::Data(data_msg) => {
+                match &data_msg {
+                    DataMessage::Block(block) => {
+                        debug!(slot = block.slot, source = msg.source, "published");
+                        crate::metrics::register_block_publication(
+                            msg.source,
+                            block.slot,
+                            block.timestamp
+                        );
+                    },
+                    DataMessage::Transaction(tx) => {
+                        debug!(
+                            slot = tx.slot,
+                            transaction_index = tx.transaction_index,
+                            source = msg.source,
+                            "published"
+                        );
+                        crate::metrics::register_tx_publication(msg.source);
+                    },
+                    DataMessage::Account(acc) => {
+                        debug!(
+                            slot = acc.slot,
+                            write_version = acc.write_version,
+                            source = msg.source,
+                            "published"
+                        );
+                        crate::metrics::register_account_publication(msg.source);
+                    },
+                    DataMessage::Rollback(rollback) => {
+                        for slot in rollback.slots.iter().copied() {
+                            commitment.mark_dead(slot, &mut emit);
+                        }
+                        continue
+                    },
+                    DataMessage::SlotStatus(status) => {
+                        commitment.update_status(status.slot, None, status.status, &mut emit);
+                        continue
+                    }
+                }
+                data_msg
+            }
+        };
+        let data_msg = Arc::new(data_msg);
+        emit(Commitment::Processed, data_msg.clone());
+        commitment.push(data_msg, &mut emit);
     }
 }
\ No newline at end of file
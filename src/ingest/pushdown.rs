@@ -0,0 +1,90 @@
+use crate::query::UpstreamFilter;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use tokio::sync::watch;
+
+
+/// Maintains the union of upstream filters of all active subscriptions
+#[derive(Clone)]
+pub struct FilterPushdown {
+    inner: Arc<Inner>
+}
+
+
+struct Inner {
+    registry: Mutex<Registry>,
+    union: watch::Sender<UpstreamFilter>
+}
+
+
+#[derive(Default)]
+struct Registry {
+    next_id: u64,
+    filters: HashMap<u64, UpstreamFilter>
+}
+
+
+impl FilterPushdown {
+    pub fn new() -> Self {
+        Self {
+            inner: Arc::new(Inner {
+                registry: Mutex::new(Registry::default()),
+                union: watch::Sender::new(UpstreamFilter::default())
+            })
+        }
+    }
+
+    /// Registers a filter for the lifetime of the returned guard
+    pub fn register(&self, filter: UpstreamFilter) -> FilterRegistration {
+        let id = {
+            let mut registry = self.inner.registry.lock().unwrap();
+            let id = registry.next_id;
+            registry.next_id += 1;
+            registry.filters.insert(id, filter);
+            self.update_union(&registry.filters);
+            id
+        };
+        FilterRegistration {
+            pushdown: self.clone(),
+            id
+        }
+    }
+
+    pub fn subscribe(&self) -> watch::Receiver<UpstreamFilter> {
+        self.inner.union.subscribe()
+    }
+
+    fn unregister(&self, id: u64) {
+        let mut registry = self.inner.registry.lock().unwrap();
+        registry.filters.remove(&id);
+        self.update_union(&registry.filters);
+    }
+
+    fn update_union(&self, filters: &HashMap<u64, UpstreamFilter>) {
+        let mut union = UpstreamFilter::default();
+        for f in filters.values() {
+            union.merge(f);
+        }
+        self.inner.union.send_if_modified(|current| {
+            if *current == union {
+                false
+            } else {
+                *current = union;
+                true
+            }
+        });
+    }
+}
+
+
+pub struct FilterRegistration {
+    pushdown: FilterPushdown,
+    id: u64
+}
+
+
+impl Drop for FilterRegistration {
+    fn drop(&mut self) {
+        self.pushdown.unregister(self.id)
+    }
+}
\ No newline at end of file
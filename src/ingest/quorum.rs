@@ -0,0 +1,307 @@
+use super::dedupe::{dedupe_key, DedupeKey, WINDOW_SLOTS};
+use super::source::{SourceMessage, SourceUpdate};
+use crate::data::{Commitment, DataMessage};
+use crate::geyser::api::SlotStatus;
+use crate::Name;
+use futures_util::{stream, Stream, StreamExt};
+use solana_transaction_error::TransactionError;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
+use tracing::warn;
+
+
+/// Maximum number of slots with pending items
+const MAX_SLOTS: usize = 4 * WINDOW_SLOTS as usize;
+
+
+/// Holds back updates until the given number of sources delivered identical ones.
+///
+/// Transactions must agree on the signature and the status, blocks on the hash.
+/// Slot status transitions (including dead slots and rollbacks) must be reported by enough sources.
+/// Without quorum all updates pass through.
+pub fn quorum(
+    input: impl Stream<Item = SourceMessage>,
+    quorum: Option<usize>
+) -> impl Stream<Item = SourceMessage>
+{
+    let mut quorum = quorum.map(Quorum::new);
+    input.flat_map(move |msg| {
+        let mut out = Vec::new();
+        match quorum.as_mut() {
+            Some(quorum) => quorum.push(msg, &mut out),
+            None => out.push(msg)
+        }
+        stream::iter(out)
+    })
+}
+
+
+struct Quorum {
+    quorum: usize,
+    head: u64,
+    slots: BTreeMap<u64, HashMap<ItemKey, Item>>
+}
+
+
+/// Identifies an item, that sources must agree on
+#[derive(Debug, Copy, Clone, Hash, Eq, PartialEq)]
+enum ItemKey {
+    Data(DedupeKey),
+    Status(SlotStatus)
+}
+
+
+#[derive(Default)]
+struct Item {
+    accepted: Option<u64>,
+    candidates: Vec<Candidate>
+}
+
+
+/// Version of an item, received from one or more sources
+struct Candidate {
+    fingerprint: u64,
+    sources: Vec<Name>,
+    msg: Option<SourceMessage>
+}
+
+
+impl Quorum {
+    fn new(quorum: usize) -> Self {
+        Self {
+            quorum,
+            head: 0,
+            slots: BTreeMap::new()
+        }
+    }
+
+    fn push(&mut self, msg: SourceMessage, out: &mut Vec<SourceMessage>) {
+        let Some((slot, key)) = item_key(&msg.update) else {
+            out.push(msg);
+            return
+        };
+
+        if slot < self.head.saturating_sub(WINDOW_SLOTS) {
+            return
+        }
+
+        let fingerprint = fingerprint(&msg.update);
+        let item = self.slots.entry(slot).or_default().entry(key).or_default();
+
+        if let Some(accepted) = item.accepted {
+            if accepted != fingerprint {
+                report_disagreement(slot, key, msg.source);
+            }
+            return
+        }
+
+        let idx = match item.candidates.iter().position(|c| c.fingerprint == fingerprint) {
+            Some(idx) => idx,
+            None => {
+                item.candidates.push(Candidate {
+                    fingerprint,
+                    sources: Vec::new(),
+                    msg: None
+                });
+                item.candidates.len() - 1
+            }
+        };
+
+        let candidate = &mut item.candidates[idx];
+        if candidate.sources.contains(&msg.source) {
+            return
+        }
+        candidate.sources.push(msg.source);
+        if candidate.sources.len() < self.quorum {
+            candidate.msg.get_or_insert(msg);
+            // single sources can't move the window, but can fill it with far-future slots
+            while self.slots.len() > MAX_SLOTS {
+                self.slots.pop_last();
+            }
+            return
+        }
+
+        out.push(candidate.msg.take().unwrap_or(msg));
+        item.accepted = Some(fingerprint);
+        for c in item.candidates.drain(..) {
+            if c.fingerprint != fingerprint {
+                for source in c.sources {
+                    report_disagreement(slot, key, source);
+                }
+            }
+        }
+
+        // only the data confirmed by the quorum moves the window
+        if slot > self.head {
+            self.head = slot;
+            self.evict();
+        }
+    }
+
+    fn evict(&mut self) {
+        let bottom = self.head.saturating_sub(WINDOW_SLOTS);
+        while self.slots.first_key_value().is_some_and(|(slot, _)| *slot < bottom) {
+            let (slot, items) = self.slots.pop_first().unwrap();
+            for (key, item) in items {
+                // sources delivered conflicting versions, none of which reached the quorum
+                if item.candidates.len() > 1 {
+                    for source in item.candidates.into_iter().flat_map(|c| c.sources) {
+                        report_disagreement(slot, key, source);
+                    }
+                }
+            }
+        }
+    }
+}
+
+
+fn report_disagreement(slot: u64, item: ItemKey, source: Name) {
+    warn!(slot, item =? item, source, "source disagrees with the quorum");
+    crate::metrics::register_quorum_disagreement(source);
+}
+
+
+fn item_key(update: &SourceUpdate) -> Option<(u64, ItemKey)> {
+    let key = match update {
+        SourceUpdate::Slot(upd) => (upd.slot, ItemKey::Status(upd.status())),
+        // relayed rollbacks start with the dead slot, followed by its descendants
+        SourceUpdate::Data(DataMessage::Rollback(rollback)) => {
+            (*rollback.slots.first()?, ItemKey::Status(SlotStatus::SlotDead))
+        },
+        SourceUpdate::Data(DataMessage::SlotStatus(status)) => {
+            let slot = status.slot;
+            let status = match status.status {
+                Commitment::Processed => SlotStatus::SlotProcessed,
+                Commitment::Confirmed => SlotStatus::SlotConfirmed,
+                Commitment::Finalized => SlotStatus::SlotFinalized
+            };
+            (slot, ItemKey::Status(status))
+        },
+        update => {
+            let (slot, key) = dedupe_key(update)?;
+            (slot, ItemKey::Data(key))
+        }
+    };
+    Some(key)
+}
+
+
+/// Hashes the content, that sources must agree on
+fn fingerprint(update: &SourceUpdate) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    match update {
+        SourceUpdate::Block(block) => {
+            block.meta.blockhash.hash(&mut hasher);
+        },
+        SourceUpdate::Data(DataMessage::Block(block)) => {
+            block.hash.hash(&mut hasher);
+        },
+        SourceUpdate::Transaction(tx) => {
+            tx.signatures.first().hash(&mut hasher);
+            tx.meta.err.as_ref().map(|err| &err.err).hash(&mut hasher);
+        },
+        SourceUpdate::Data(DataMessage::Transaction(tx)) => {
+            let signature = tx.transaction.signature.as_ref().and_then(|sig| bs58::decode(sig).into_vec().ok());
+            signature.as_ref().hash(&mut hasher);
+            // use the binary encoding of geyser sources
+            let err = tx.transaction.err.as_ref().map(|err| {
+                match serde_json::from_str::<TransactionError>(err) {
+                    Ok(err) => bincode::serialize(&err).expect("serialization is infallible"),
+                    Err(_) => err.as_bytes().to_vec()
+                }
+            });
+            err.as_ref().hash(&mut hasher);
+        },
+        // account keys and slot statuses already identify the content
+        _ => {}
+    }
+    hasher.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::RollbackData;
+    use crate::ingest::testing::{data, describe, slot, tx};
+    use std::sync::Arc;
+
+    fn push(quorum: &mut Quorum, source: Name, slot: u64, index: u64) -> Vec<String> {
+        push_update(quorum, source, tx(slot, index))
+    }
+
+    fn push_update(quorum: &mut Quorum, source: Name, update: SourceUpdate) -> Vec<String> {
+        let mut out = Vec::new();
+        quorum.push(SourceMessage::new(source, update), &mut out);
+        out.iter().map(|msg| match &msg.update {
+            SourceUpdate::Transaction(tx) => format!("tx {}:{}", tx.slot, tx.index),
+            SourceUpdate::Slot(upd) => format!("slot {} {:?}", upd.slot, upd.status()),
+            SourceUpdate::Data(msg) => describe(msg),
+            upd => format!("{:?}", upd)
+        }).collect()
+    }
+
+    #[test]
+    fn accept_on_quorum() {
+        let mut quorum = Quorum::new(2);
+        assert!(push(&mut quorum, "a", 10, 0).is_empty());
+        assert!(push(&mut quorum, "a", 10, 0).is_empty());
+        assert_eq!(push(&mut quorum, "b", 10, 0), ["tx 10:0"]);
+        assert!(push(&mut quorum, "c", 10, 0).is_empty());
+    }
+
+    #[test]
+    fn far_future_slot() {
+        let mut quorum = Quorum::new(2);
+        assert!(push(&mut quorum, "bad", u64::MAX, 0).is_empty());
+        for slot in 1000..1100 {
+            assert!(push(&mut quorum, "bad", slot, 0).is_empty());
+        }
+        assert!(quorum.slots.len() <= MAX_SLOTS);
+
+        assert!(push(&mut quorum, "a", 10, 0).is_empty());
+        assert_eq!(push(&mut quorum, "b", 10, 0), ["tx 10:0"]);
+        assert!(push(&mut quorum, "a", 11, 0).is_empty());
+        assert_eq!(push(&mut quorum, "b", 11, 0), ["tx 11:0"]);
+        assert_eq!(quorum.head, 11);
+    }
+
+    #[test]
+    fn window() {
+        let mut quorum = Quorum::new(2);
+        assert!(push(&mut quorum, "a", 10, 0).is_empty());
+        push(&mut quorum, "a", 100, 0);
+        assert_eq!(push(&mut quorum, "b", 100, 0), ["tx 100:0"]);
+        // slot 10 was evicted and is below the window now
+        assert!(push(&mut quorum, "b", 10, 0).is_empty());
+        assert!(push(&mut quorum, "a", 10, 0).is_empty());
+    }
+
+    #[test]
+    fn slot_status_quorum() {
+        let rollback = || SourceUpdate::Data(DataMessage::Rollback(RollbackData {
+            slots: vec![10, 11]
+        }));
+        let mut quorum = Quorum::new(2);
+        // a single source can't abandon or finalize slots
+        assert!(push_update(&mut quorum, "bad", slot(10, 9, SlotStatus::SlotDead)).is_empty());
+        assert!(push_update(&mut quorum, "bad", rollback()).is_empty());
+        assert!(push_update(&mut quorum, "bad", slot(12, 9, SlotStatus::SlotFinalized)).is_empty());
+        assert!(push_update(&mut quorum, "bad", slot(12, 9, SlotStatus::SlotFinalized)).is_empty());
+
+        assert_eq!(
+            push_update(&mut quorum, "a", slot(12, 9, SlotStatus::SlotFinalized)),
+            ["slot 12 SlotFinalized"]
+        );
+        assert!(push_update(&mut quorum, "b", slot(12, 9, SlotStatus::SlotFinalized)).is_empty());
+        // a relayed rollback agrees with the dead slot status
+        assert_eq!(push_update(&mut quorum, "relay", rollback()), ["slot 10 SlotDead"]);
+    }
+
+    #[test]
+    fn relayed_transaction_fingerprint() {
+        let relayed = Arc::try_unwrap(data(tx(10, 0))).unwrap();
+        assert_eq!(fingerprint(&tx(10, 0)), fingerprint(&SourceUpdate::Data(relayed)));
+        let other = Arc::try_unwrap(data(tx(10, 1))).unwrap();
+        assert_ne!(fingerprint(&tx(10, 0)), fingerprint(&SourceUpdate::Data(other)));
+    }
+}
\ No newline at end of file
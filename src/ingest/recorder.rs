@@ -0,0 +1,107 @@
+use crate::geyser::api::SubscribeUpdate;
+use anyhow::Context;
+use prost::Message;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::time::{Duration, Instant, SystemTime};
+use tokio::sync::mpsc::error::TryRecvError;
+use tokio::sync::mpsc::{channel, Receiver, Sender};
+use tracing::error;
+
+
+const FLUSH_INTERVAL: Duration = Duration::from_secs(1);
+
+
+/// Writes received updates to a file as a length-delimited protobuf stream.
+///
+/// Files with `.zst` extension are zstd-compressed.
+pub struct Recorder {
+    tx: Sender<Vec<u8>>,
+    #[cfg_attr(not(test), allow(dead_code))]
+    writer: std::thread::JoinHandle<()>
+}
+
+
+impl Recorder {
+    pub fn create(path: &Path) -> anyhow::Result<Self> {
+        let file = File::options()
+            .create(true)
+            .append(true)
+            .open(path)
+            .with_context(|| format!("failed to open recording file {}", path.display()))?;
+
+        let writer: Box<dyn Write + Send> = if is_compressed(path) {
+            Box::new(zstd::Encoder::new(file, 0)?.auto_finish())
+        } else {
+            Box::new(BufWriter::new(file))
+        };
+
+        let (tx, rx) = channel(1000);
+
+        let writer = std::thread::Builder::new()
+            .name("recorder".to_string())
+            .spawn(move || write_loop(writer, rx))?;
+
+        Ok(Self {
+            tx,
+            writer
+        })
+    }
+
+    /// Records the given update.
+    ///
+    /// Sets `.created_at` to the current time when the source didn't provide it,
+    /// so that the recording could be replayed with the original timing.
+    pub async fn record(&self, upd: &mut SubscribeUpdate) {
+        if upd.created_at.is_none() {
+            upd.created_at = Some(SystemTime::now().into());
+        }
+        let bytes = upd.encode_length_delimited_to_vec();
+        // write failures are reported by the writer thread
+        let _ = self.tx.send(bytes).await;
+    }
+
+    /// Waits until all recorded updates are written
+    #[cfg(test)]
+    pub fn finish(self) {
+        drop(self.tx);
+        self.writer.join().unwrap()
+    }
+}
+
+
+fn write_loop(mut writer: Box<dyn Write + Send>, mut rx: Receiver<Vec<u8>>) {
+    let mut last_flush = Instant::now();
+    loop {
+        let bytes = match rx.try_recv() {
+            Ok(bytes) => bytes,
+            Err(TryRecvError::Empty) => {
+                if last_flush.elapsed() > FLUSH_INTERVAL {
+                    if let Err(err) = writer.flush() {
+                        error!(err =? err, "failed to flush recording");
+                        return
+                    }
+                    last_flush = Instant::now();
+                }
+                match rx.blocking_recv() {
+                    Some(bytes) => bytes,
+                    None => break
+                }
+            },
+            Err(TryRecvError::Disconnected) => break
+        };
+        if let Err(err) = writer.write_all(&bytes) {
+            error!(err =? err, "failed to write recording");
+            return
+        }
+    }
+    if let Err(err) = writer.flush() {
+        error!(err =? err, "failed to flush recording");
+    }
+}
+
+
+pub fn is_compressed(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "zst")
+}
\ No newline at end of file
@@ -0,0 +1,339 @@
+use super::recorder::is_compressed;
+use super::source::{convert_update, SourceMessage, SourceUpdate, VoteFilter};
+use crate::geyser::api::SubscribeUpdate;
+use crate::geyser::MAX_MESSAGE_SIZE;
+use crate::Name;
+use anyhow::Context;
+use prost::Message;
+use prost_types::Timestamp;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind, Read};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc::{Receiver, Sender};
+use tokio::time::{sleep_until, Instant};
+use tracing::{error, info, instrument, warn};
+
+
+#[derive(Debug, Clone)]
+pub struct ReplayOptions {
+    /// Recording file
+    pub path: PathBuf,
+    /// Replay speed relative to the original one (zero means as fast as possible)
+    pub speed: f64,
+    /// Pause before the replay, e.g. to let clients subscribe
+    pub start_delay: Duration,
+    /// Whether to replay vote transactions
+    pub votes: bool,
+    /// Whether to replay account updates
+    pub accounts: bool
+}
+
+
+#[instrument(name = "source", skip_all, fields(source = name))]
+pub async fn replay_loop(
+    output: Sender<SourceMessage>,
+    name: Name,
+    options: ReplayOptions
+) -> anyhow::Result<()>
+{
+    tokio::time::sleep(options.start_delay).await;
+
+    let mut updates = read_recording(&options.path)?;
+    let mut clock = ReplayClock::new(options.speed);
+    let mut vote_filter = VoteFilter::default();
+
+    while let Some(upd) = updates.recv().await {
+        let upd = upd?;
+        
+        clock.wait(upd.created_at).await;
+
+        let Some(upd) = upd.update_oneof else {
+            continue
+        };
+
+        let updates = match convert_update(upd) {
+            Ok(updates) => updates,
+            Err(err) => {
+                crate::metrics::register_data_source_error(name);
+                error!(err =? err, "skipping malformed update");
+                continue
+            }
+        };
+
+        for mut update in updates {
+            if !options.votes && !vote_filter.accept(&mut update) {
+                continue
+            }
+            if matches!(update, SourceUpdate::Account(_)) && !options.accounts {
+                continue
+            }
+            if let SourceUpdate::Block(block) = &mut update {
+                // the recorded subscription might have been narrowed, e.g. votes were filtered upstream
+                block.transaction_count = None;
+            }
+
+            let msg = SourceMessage::new(name, update);
+
+            if output.send(msg).await.is_err() {
+                return Ok(())
+            }
+        }
+    }
+
+    info!("replay finished");
+    Ok(())
+}
+
+
+/// Reads recorded updates in a background thread.
+///
+/// A read failure is delivered as the last item.
+pub fn read_recording(path: &Path) -> anyhow::Result<Receiver<anyhow::Result<SubscribeUpdate>>> {
+    let file = File::open(path).with_context(|| {
+        format!("failed to open recording file {}", path.display())
+    })?;
+
+    let mut reader: Box<dyn Read + Send> = if is_compressed(path) {
+        Box::new(zstd::Decoder::new(file)?)
+    } else {
+        Box::new(BufReader::new(file))
+    };
+
+    let (tx, rx) = tokio::sync::mpsc::channel(1000);
+
+    tokio::task::spawn_blocking(move || {
+        let mut buf = Vec::new();
+        loop {
+            let upd = match read_message(&mut reader, &mut buf) {
+                Ok(true) => SubscribeUpdate::decode(buf.as_slice()).context("malformed recording"),
+                Ok(false) => return,
+                Err(err) if err.kind() == ErrorKind::UnexpectedEof => {
+                    warn!("recording is truncated");
+                    return
+                },
+                Err(err) => Err(err.into())
+            };
+            let failed = upd.is_err();
+            if tx.blocking_send(upd).is_err() || failed {
+                return
+            }
+        }
+    });
+
+    Ok(rx)
+}
+
+
+/// Reproduces the original timing of recorded updates
+pub struct ReplayClock {
+    speed: f64,
+    start: Option<(SystemTime, Instant)>
+}
+
+
+impl ReplayClock {
+    /// Creates a clock running at the given speed relative to the original one
+    /// (zero means as fast as possible)
+    pub fn new(speed: f64) -> Self {
+        Self {
+            speed,
+            start: None
+        }
+    }
+
+    /// Waits until the update created at the given time is due
+    pub async fn wait(&mut self, created_at: Option<Timestamp>) {
+        if self.speed <= 0.0 {
+            return
+        }
+        let Some(created_at) = created_at.and_then(|ts| SystemTime::try_from(ts).ok()) else {
+            return
+        };
+        match self.start {
+            Some((start_time, start)) => {
+                let offset = created_at.duration_since(start_time).unwrap_or_default();
+                sleep_until(start + offset.div_f64(self.speed)).await;
+            },
+            None => {
+                self.start = Some((created_at, Instant::now()));
+            }
+        }
+    }
+}
+
+
+/// Reads the next length-delimited message into `buf`.
+///
+/// Returns `false` at the end of the stream.
+fn read_message(reader: &mut impl Read, buf: &mut Vec<u8>) -> std::io::Result<bool> {
+    let mut len = 0u64;
+    let mut shift = 0;
+    loop {
+        let mut byte = [0u8];
+        if reader.read(&mut byte)? == 0 {
+            return if shift == 0 {
+                Ok(false)
+            } else {
+                Err(ErrorKind::UnexpectedEof.into())
+            }
+        }
+        len |= ((byte[0] & 0x7f) as u64) << shift;
+        if byte[0] & 0x80 == 0 {
+            break
+        }
+        shift += 7;
+        if shift > 63 {
+            return Err(std::io::Error::new(ErrorKind::InvalidData, "invalid message length"))
+        }
+    }
+    if len > MAX_MESSAGE_SIZE as u64 {
+        return Err(std::io::Error::new(ErrorKind::InvalidData, "message is too large"))
+    }
+    buf.resize(len as usize, 0);
+    reader.read_exact(buf)?;
+    Ok(true)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Commitment;
+    use crate::geyser::api::subscribe_update::UpdateOneof;
+    use crate::geyser::api::{SlotStatus, SubscribeUpdateSlot};
+    use crate::geyser::{mock_block, mock_transaction, MockBlock, MockTransaction};
+    use crate::ingest::broadcast::Broadcast;
+    use crate::ingest::buffer::BufferOptions;
+    use crate::ingest::processing::processing_loop;
+    use crate::ingest::recorder::Recorder;
+    use crate::ingest::testing::describe;
+    use std::path::Path;
+    use tokio_stream::wrappers::ReceiverStream;
+
+    fn slot(slot: u64, status: SlotStatus) -> UpdateOneof {
+        UpdateOneof::Slot(SubscribeUpdateSlot {
+            slot,
+            parent: Some(slot - 1),
+            status: status as i32,
+            dead_error: None
+        })
+    }
+
+    fn tx(slot: u64, index: u64, is_vote: bool) -> UpdateOneof {
+        UpdateOneof::Transaction(mock_transaction(MockTransaction {
+            slot,
+            index,
+            accounts: Vec::new(),
+            is_vote,
+            failed: false
+        }, true))
+    }
+
+    fn block(slot: u64) -> UpdateOneof {
+        UpdateOneof::BlockMeta(mock_block(MockBlock {
+            slot,
+            parent_slot: None,
+            blockhash: None,
+            parent_blockhash: None,
+            block_time: Some(0),
+            block_height: None,
+            executed_transaction_count: 3
+        }))
+    }
+
+    fn fixture() -> Vec<UpdateOneof> {
+        vec![
+            slot(10, SlotStatus::SlotProcessed),
+            tx(10, 0, false),
+            tx(10, 1, true),
+            tx(10, 2, false),
+            block(10),
+            slot(11, SlotStatus::SlotProcessed),
+            tx(11, 0, false),
+            block(11),
+            slot(10, SlotStatus::SlotConfirmed)
+        ]
+    }
+
+    async fn record(path: &Path) {
+        let _ = std::fs::remove_file(path);
+        let recorder = Recorder::create(path).unwrap();
+        for upd in fixture() {
+            let mut upd = SubscribeUpdate {
+                filters: Vec::new(),
+                update_oneof: Some(upd),
+                created_at: None
+            };
+            recorder.record(&mut upd).await;
+        }
+        recorder.finish();
+    }
+
+    /// Replays the recording through the processing loop
+    /// and returns published messages of each commitment level
+    async fn replay(path: &Path) -> Vec<String> {
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 0,
+            max_bytes: 0
+        });
+        let mut receivers = [Commitment::Processed, Commitment::Confirmed].map(|level| {
+            (level, broadcast.subscribe(level))
+        });
+        let (tx, rx) = tokio::sync::mpsc::channel(100);
+        let options = ReplayOptions {
+            path: path.to_path_buf(),
+            speed: 0.0,
+            start_delay: Duration::ZERO,
+            votes: false,
+            accounts: false
+        };
+        replay_loop(tx, "file", options).await.unwrap();
+        processing_loop(broadcast, ReceiverStream::new(rx), None, None).await;
+
+        let mut published = Vec::new();
+        for (level, rx) in receivers.iter_mut() {
+            while let Ok(msg) = rx.try_recv() {
+                published.push(format!("{:?}: {}", level, describe(&msg)));
+            }
+        }
+        published
+    }
+
+    #[tokio::test]
+    async fn record_and_replay() {
+        let dir = std::env::temp_dir();
+        let plain = dir.join(format!("sqd-spray-replay-{}.bin", std::process::id()));
+        let compressed = dir.join(format!("sqd-spray-replay-{}.bin.zst", std::process::id()));
+
+        record(&plain).await;
+        record(&compressed).await;
+
+        let zstd_magic = [0x28, 0xb5, 0x2f, 0xfd];
+        assert_ne!(std::fs::read(&plain).unwrap()[..4], zstd_magic);
+        assert_eq!(std::fs::read(&compressed).unwrap()[..4], zstd_magic);
+
+        let published = replay(&plain).await;
+        assert_eq!(published, replay(&compressed).await);
+        assert_eq!(published, [
+            "Processed: tx 10:0",
+            "Processed: tx 10:2",
+            "Processed: block 10",
+            "Processed: tx 11:0",
+            "Processed: block 11",
+            "Processed: Confirmed 10",
+            "Confirmed: tx 10:0",
+            "Confirmed: tx 10:2",
+            "Confirmed: block 10"
+        ]);
+
+        let _ = std::fs::remove_file(plain);
+        let _ = std::fs::remove_file(compressed);
+    }
+
+    #[test]
+    fn oversized_message() {
+        let mut reader: &[u8] = &[0xff, 0xff, 0xff, 0xff, 0x7f];
+        let err = read_message(&mut reader, &mut Vec::new()).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+}
\ No newline at end of file
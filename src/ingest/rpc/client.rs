@@ -0,0 +1,98 @@
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+
+/// Minimal Solana JSON-RPC client
+pub struct RpcClient {
+    http: reqwest::Client,
+    url: String,
+    next_id: AtomicU64
+}
+
+
+#[derive(Debug)]
+pub enum RpcError {
+    /// Error object returned by the node
+    Node {
+        code: i64,
+        message: String
+    },
+    Transport(anyhow::Error)
+}
+
+
+impl std::fmt::Display for RpcError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcError::Node { code, message } => write!(f, "RPC error {}: {}", code, message),
+            RpcError::Transport(err) => write!(f, "{:#}", err)
+        }
+    }
+}
+
+
+impl std::error::Error for RpcError {}
+
+
+#[derive(Deserialize)]
+struct Response<T> {
+    result: Option<T>,
+    error: Option<ErrorObject>
+}
+
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String
+}
+
+
+impl RpcClient {
+    pub fn new(url: String, timeout: Duration) -> anyhow::Result<Self> {
+        let http = reqwest::Client::builder()
+            .timeout(timeout)
+            .build()?;
+        Ok(Self {
+            http,
+            url,
+            next_id: AtomicU64::new(0)
+        })
+    }
+
+    /// Performs a call.
+    ///
+    /// Returns `None` when the call succeeded with the `null` result.
+    pub async fn call<T: DeserializeOwned>(&self, method: &str, params: Value) -> Result<Option<T>, RpcError> {
+        let request = json!({
+            "jsonrpc": "2.0",
+            "id": self.next_id.fetch_add(1, Ordering::Relaxed),
+            "method": method,
+            "params": params
+        });
+
+        let response: Response<T> = async {
+            let res = self.http.post(&self.url)
+                .json(&request)
+                .send()
+                .await?
+                .error_for_status()?
+                .json()
+                .await?;
+            Ok(res)
+        }.await.map_err(|err: reqwest::Error| {
+            RpcError::Transport(anyhow::Error::new(err).context(format!("{} call failed", method)))
+        })?;
+
+        match response.error {
+            Some(err) => Err(RpcError::Node {
+                code: err.code,
+                message: err.message
+            }),
+            None => Ok(response.result)
+        }
+    }
+}
\ No newline at end of file
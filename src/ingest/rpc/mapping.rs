@@ -0,0 +1,178 @@
+use super::model;
+use crate::geyser::api::SubscribeUpdateBlockMeta;
+use crate::geyser::solana::storage::confirmed_block::{BlockHeight, CompiledInstruction, InnerInstruction, InnerInstructions, MessageAddressTableLookup, MessageHeader, ReturnData, Reward, RewardType, Rewards, TokenBalance, TransactionError, TransactionStatusMeta, UiTokenAmount, UnixTimestamp};
+use crate::ingest::source::TransactionUpdate;
+use anyhow::{anyhow, Context};
+use base64::Engine;
+
+
+const VOTE_PROGRAM: &str = "Vote111111111111111111111111111111111111111";
+
+
+pub fn map_block_meta(slot: u64, block: &mut model::Block) -> SubscribeUpdateBlockMeta {
+    let rewards = std::mem::take(&mut block.rewards).into_iter().map(|r| {
+        let reward_type = match r.reward_type.as_deref().map(|t| t.to_ascii_lowercase()).as_deref() {
+            Some("fee") => RewardType::Fee,
+            Some("rent") => RewardType::Rent,
+            Some("staking") => RewardType::Staking,
+            Some("voting") => RewardType::Voting,
+            _ => RewardType::Unspecified
+        };
+        Reward {
+            pubkey: r.pubkey,
+            lamports: r.lamports,
+            post_balance: r.post_balance,
+            reward_type: reward_type as i32,
+            commission: r.commission.map(|c| c.to_string()).unwrap_or_default()
+        }
+    }).collect();
+
+    SubscribeUpdateBlockMeta {
+        slot,
+        blockhash: block.blockhash.clone(),
+        rewards: Some(Rewards {
+            rewards,
+            num_partitions: None
+        }),
+        block_time: block.block_time.map(|timestamp| UnixTimestamp {
+            timestamp
+        }),
+        block_height: block.block_height.map(|block_height| BlockHeight {
+            block_height
+        }),
+        parent_slot: block.parent_slot,
+        parent_blockhash: block.previous_blockhash.clone(),
+        executed_transaction_count: block.transactions.len() as u64,
+        entries_count: 0
+    }
+}
+
+
+pub fn map_transaction(
+    slot: u64,
+    index: usize,
+    tx: model::TransactionWithMeta
+) -> anyhow::Result<TransactionUpdate>
+{
+    let meta = tx.meta.ok_or_else(|| anyhow!("transaction meta is missing"))?;
+    let message = tx.transaction.message;
+
+    let is_vote = tx.transaction.signatures.len() < 3
+        && message.instructions.len() == 1
+        && message.account_keys.get(message.instructions[0].program_id_index as usize)
+            .is_some_and(|program| program == VOTE_PROGRAM);
+
+    Ok(TransactionUpdate {
+        slot,
+        index,
+        is_vote,
+        signatures: tx.transaction.signatures.iter().map(|s| base58(s)).collect::<anyhow::Result<_>>()?,
+        header: MessageHeader {
+            num_required_signatures: message.header.num_required_signatures,
+            num_readonly_signed_accounts: message.header.num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts: message.header.num_readonly_unsigned_accounts
+        },
+        account_keys: message.account_keys.iter().map(|k| base58(k)).collect::<anyhow::Result<_>>()?,
+        recent_blockhash: base58(&message.recent_blockhash)?,
+        instructions: message.instructions.into_iter().map(|ins| {
+            Ok(CompiledInstruction {
+                program_id_index: ins.program_id_index,
+                accounts: ins.accounts,
+                data: base58(&ins.data)?
+            })
+        }).collect::<anyhow::Result<_>>()?,
+        versioned: tx.version.is_some_and(|v| v.is_number()),
+        address_table_lookups: message.address_table_lookups.into_iter().map(|lookup| {
+            Ok(MessageAddressTableLookup {
+                account_key: base58(&lookup.account_key)?,
+                writable_indexes: lookup.writable_indexes,
+                readonly_indexes: lookup.readonly_indexes
+            })
+        }).collect::<anyhow::Result<_>>()?,
+        meta: map_meta(meta)?
+    })
+}
+
+
+fn map_meta(meta: model::TransactionMeta) -> anyhow::Result<TransactionStatusMeta> {
+    let err = match meta.err {
+        None => None,
+        Some(err) => {
+            let err: solana_transaction_error::TransactionError = serde_json::from_value(err)
+                .context("failed to parse transaction error")?;
+            Some(TransactionError {
+                err: bincode::serialize(&err).expect("serialization is infallible")
+            })
+        }
+    };
+
+    let (loaded_writable_addresses, loaded_readonly_addresses) = match meta.loaded_addresses {
+        Some(addresses) => (
+            addresses.writable.iter().map(|a| base58(a)).collect::<anyhow::Result<_>>()?,
+            addresses.readonly.iter().map(|a| base58(a)).collect::<anyhow::Result<_>>()?
+        ),
+        None => (Vec::new(), Vec::new())
+    };
+
+    let return_data = meta.return_data.map(|rd| -> anyhow::Result<_> {
+        Ok(ReturnData {
+            program_id: base58(&rd.program_id)?,
+            data: base64::engine::general_purpose::STANDARD.decode(&rd.data.0)
+                .context("invalid base64 string")?
+        })
+    }).transpose()?;
+
+    Ok(TransactionStatusMeta {
+        err,
+        fee: meta.fee,
+        pre_balances: meta.pre_balances,
+        post_balances: meta.post_balances,
+        inner_instructions_none: meta.inner_instructions.is_none(),
+        inner_instructions: meta.inner_instructions.unwrap_or_default().into_iter().map(|inner| {
+            Ok(InnerInstructions {
+                index: inner.index,
+                instructions: inner.instructions.into_iter().map(|ins| {
+                    Ok(InnerInstruction {
+                        program_id_index: ins.program_id_index,
+                        accounts: ins.accounts,
+                        data: base58(&ins.data)?,
+                        stack_height: ins.stack_height
+                    })
+                }).collect::<anyhow::Result<_>>()?
+            })
+        }).collect::<anyhow::Result<_>>()?,
+        log_messages_none: meta.log_messages.is_none(),
+        log_messages: meta.log_messages.unwrap_or_default(),
+        pre_token_balances: map_token_balances(meta.pre_token_balances),
+        post_token_balances: map_token_balances(meta.post_token_balances),
+        rewards: Vec::new(),
+        loaded_writable_addresses,
+        loaded_readonly_addresses,
+        return_data_none: return_data.is_none(),
+        return_data,
+        compute_units_consumed: meta.compute_units_consumed
+    })
+}
+
+
+fn map_token_balances(balances: Option<Vec<model::TokenBalance>>) -> Vec<TokenBalance> {
+    balances.unwrap_or_default().into_iter().map(|b| {
+        TokenBalance {
+            account_index: b.account_index,
+            mint: b.mint,
+            ui_token_amount: Some(UiTokenAmount {
+                ui_amount: b.ui_token_amount.ui_amount.unwrap_or_default(),
+                decimals: b.ui_token_amount.decimals,
+                amount: b.ui_token_amount.amount,
+                ui_amount_string: b.ui_token_amount.ui_amount_string
+            }),
+            owner: b.owner.unwrap_or_default(),
+            program_id: b.program_id.unwrap_or_default()
+        }
+    }).collect()
+}
+
+
+fn base58(s: &str) -> anyhow::Result<Vec<u8>> {
+    bs58::decode(s).into_vec().with_context(|| format!("invalid base58 string - {}", s))
+}
\ No newline at end of file
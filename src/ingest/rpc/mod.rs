@@ -0,0 +1,7 @@
+mod client;
+mod mapping;
+mod model;
+mod source;
+
+
+pub use source::*;
\ No newline at end of file
@@ -0,0 +1,329 @@
+use super::client::{RpcClient, RpcError};
+use super::mapping::{map_block_meta, map_transaction};
+use super::model;
+use crate::data::Commitment;
+use crate::geyser::api::{SlotStatus, SubscribeUpdateSlot};
+use crate::ingest::source::{Backoff, BlockUpdate, SourceMessage, SourceUpdate};
+use crate::Name;
+use anyhow::Context;
+use serde_json::json;
+use std::time::Duration;
+use tokio::sync::mpsc::Sender;
+use tracing::{debug, error, instrument, warn};
+
+
+/// Maximum number of slots to catch up after an outage
+const MAX_CATCH_UP_SLOTS: u64 = 150;
+
+
+#[derive(Debug, Clone)]
+pub struct RpcSourceOptions {
+    /// JSON-RPC endpoint
+    pub url: String,
+    /// Commitment of fetched blocks (either confirmed or finalized)
+    pub commitment: Commitment,
+    /// Pause between polls of the chain tip
+    pub poll_interval: Duration,
+    /// Whether to ingest vote transactions
+    pub votes: bool
+}
+
+
+#[instrument(name = "source", skip_all, fields(source = name))]
+pub async fn rpc_source_loop(
+    output: Sender<SourceMessage>,
+    name: Name,
+    options: RpcSourceOptions
+) -> anyhow::Result<()>
+{
+    let client = RpcClient::new(options.url.clone(), Duration::from_secs(30))?;
+
+    let mut poller = Poller {
+        client,
+        output,
+        name,
+        options,
+        next_slot: None,
+        last_published: None,
+        last_finalized: 0
+    };
+
+    let mut backoff = Backoff::new(true);
+    let mut update_received = false;
+    loop {
+        match poller.poll().await {
+            Ok(true) => {
+                update_received = true;
+                tokio::time::sleep(poller.options.poll_interval).await;
+            },
+            Ok(false) => return Ok(()),
+            Err(err) => {
+                backoff.failure(name, err, update_received).await?;
+                update_received = false;
+            }
+        }
+    }
+}
+
+struct Poller {
+    client: RpcClient,
+    output: Sender<SourceMessage>,
+    name: Name,
+    options: RpcSourceOptions,
+    next_slot: Option<u64>,
+    last_published: Option<u64>,
+    last_finalized: u64
+}
+
+
+enum Fetch {
+    Block(Box<model::Block>),
+    Skipped,
+    NotAvailable
+}
+
+
+impl Poller {
+    /// Publishes all blocks up to the current chain tip.
+    ///
+    /// Returns `false` when the output channel was closed.
+    async fn poll(&mut self) -> anyhow::Result<bool> {
+        let tip = self.get_slot(self.options.commitment).await?;
+
+        let mut slot = self.next_slot.unwrap_or(tip);
+        if tip.saturating_sub(slot) > MAX_CATCH_UP_SLOTS {
+            let new_slot = tip - MAX_CATCH_UP_SLOTS;
+            warn!(skipped = new_slot - slot, "too far behind the chain tip, skipping blocks");
+            slot = new_slot;
+        }
+
+        if slot <= tip {
+            // skipped slots below the tip are reported as not available until they are finalized,
+            // so only the produced ones are fetched
+            let produced = self.get_blocks(slot, tip).await?;
+            let mut complete = true;
+            for slot in produced {
+                match self.get_block(slot).await? {
+                    Fetch::Block(block) => {
+                        if !self.publish(slot, *block).await {
+                            return Ok(false)
+                        }
+                    },
+                    Fetch::Skipped => {
+                        debug!(slot, "block is missing");
+                    },
+                    Fetch::NotAvailable => {
+                        complete = false;
+                        break
+                    }
+                }
+                self.next_slot = Some(slot + 1);
+            }
+            if complete {
+                self.next_slot = Some(tip + 1);
+            }
+        }
+
+        if self.options.commitment == Commitment::Confirmed {
+            let finalized = self.get_slot(Commitment::Finalized).await?;
+            // finalization of a block implies finalization of its ancestors
+            if let Some(last) = self.last_published.filter(|last| *last > self.last_finalized) {
+                let slot = last.min(finalized);
+                if slot > self.last_finalized {
+                    self.last_finalized = slot;
+                    if !self.send(slot_update(slot, None, SlotStatus::SlotFinalized)).await {
+                        return Ok(false)
+                    }
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    async fn publish(&mut self, slot: u64, mut block: model::Block) -> bool {
+        let meta = map_block_meta(slot, &mut block);
+        let parent_slot = block.parent_slot;
+        let mut dropped_votes = 0;
+
+        for (index, tx) in block.transactions.into_iter().enumerate() {
+            match map_transaction(slot, index, tx) {
+                Ok(tx) => {
+                    if tx.is_vote && !self.options.votes {
+                        dropped_votes += 1;
+                        continue
+                    }
+                    if !self.send(SourceUpdate::Transaction(tx)).await {
+                        return false
+                    }
+                },
+                Err(err) => {
+                    error!(
+                        slot,
+                        transaction_index = index,
+                        err =? err,
+                        "failed to map transaction"
+                    );
+                    crate::metrics::register_mapping_error(self.name);
+                }
+            }
+        }
+
+        debug!(slot, "received");
+
+        let mut block = BlockUpdate::new(meta);
+        block.transaction_count = block.transaction_count.map(|count| count - dropped_votes);
+
+        if !self.send(SourceUpdate::Block(block)).await {
+            return false
+        }
+
+        let status = match self.options.commitment {
+            Commitment::Finalized => SlotStatus::SlotFinalized,
+            _ => SlotStatus::SlotConfirmed
+        };
+
+        self.last_published = Some(slot);
+        self.send(slot_update(slot, Some(parent_slot), status)).await
+    }
+
+    async fn send(&self, update: SourceUpdate) -> bool {
+        let msg = SourceMessage::new(self.name, update);
+        self.output.send(msg).await.is_ok()
+    }
+
+    async fn get_slot(&self, commitment: Commitment) -> anyhow::Result<u64> {
+        self.client.call("getSlot", json!([{ "commitment": commitment }]))
+            .await?
+            .context("got null result for getSlot")
+    }
+
+    /// Lists produced slots within the given inclusive range
+    async fn get_blocks(&self, from: u64, to: u64) -> anyhow::Result<Vec<u64>> {
+        self.client.call("getBlocks", json!([from, to, { "commitment": self.options.commitment }]))
+            .await?
+            .context("got null result for getBlocks")
+    }
+
+    async fn get_block(&self, slot: u64) -> anyhow::Result<Fetch> {
+        let result = self.client.call("getBlock", json!([
+            slot,
+            {
+                "commitment": self.options.commitment,
+                "encoding": "json",
+                "transactionDetails": "full",
+                "rewards": true,
+                "maxSupportedTransactionVersion": 0
+            }
+        ])).await;
+
+        match result {
+            Ok(Some(block)) => Ok(Fetch::Block(Box::new(block))),
+            Ok(None) => Ok(Fetch::NotAvailable),
+            // slot was skipped, or missing in long-term storage
+            Err(RpcError::Node { code: -32007 | -32009, .. }) => Ok(Fetch::Skipped),
+            // block is not available yet
+            Err(RpcError::Node { code: -32004 | -32014, .. }) => Ok(Fetch::NotAvailable),
+            Err(err) => Err(anyhow::Error::new(err).context(format!("failed to fetch block {}", slot)))
+        }
+    }
+}
+
+
+fn slot_update(slot: u64, parent: Option<u64>, status: SlotStatus) -> SourceUpdate {
+    SourceUpdate::Slot(SubscribeUpdateSlot {
+        slot,
+        parent,
+        status: status as i32,
+        dead_error: None
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonrpsee::server::Server;
+    use jsonrpsee::types::ErrorObject;
+    use jsonrpsee::RpcModule;
+    use serde_json::Value;
+
+    const PRODUCED: [u64; 4] = [100, 102, 103, 105];
+
+    /// Starts a fake JSON-RPC node with slots 101 and 104 skipped
+    async fn start_node() -> String {
+        let mut rpc = RpcModule::new(());
+        rpc.register_method("getSlot", |params, _, _| {
+            let commitment = params.sequence().next::<Value>().unwrap()["commitment"].clone();
+            if commitment == "finalized" { 102 } else { 105 }
+        }).unwrap();
+        rpc.register_method("getBlocks", |params, _, _| {
+            let mut params = params.sequence();
+            let from: u64 = params.next().unwrap();
+            let to: u64 = params.next().unwrap();
+            PRODUCED.into_iter().filter(|slot| (from..=to).contains(slot)).collect::<Vec<_>>()
+        }).unwrap();
+        rpc.register_method("getBlock", |params, _, _| {
+            let slot: u64 = params.sequence().next().unwrap();
+            let Some(i) = PRODUCED.iter().position(|s| *s == slot) else {
+                // confirmed nodes report skipped slots as not available
+                return Err(ErrorObject::owned(-32004, "Block not available", None::<()>))
+            };
+            Ok(json!({
+                "blockhash": bs58::encode(slot.to_le_bytes()).into_string(),
+                "previousBlockhash": "11111111111111111111111111111111",
+                "parentSlot": PRODUCED[i.saturating_sub(1)],
+                "blockTime": 0,
+                "blockHeight": slot
+            }))
+        }).unwrap();
+
+        let server = Server::builder().build("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", server.local_addr().unwrap());
+        let handle = server.start(rpc);
+        tokio::spawn(handle.stopped());
+        url
+    }
+
+    #[tokio::test]
+    async fn skipped_slots() {
+        let url = start_node().await;
+        let (output, mut rx) = tokio::sync::mpsc::channel(100);
+        let mut poller = Poller {
+            client: RpcClient::new(url.clone(), Duration::from_secs(5)).unwrap(),
+            output,
+            name: "rpc",
+            options: RpcSourceOptions {
+                url,
+                commitment: Commitment::Confirmed,
+                poll_interval: Duration::ZERO,
+                votes: false
+            },
+            next_slot: Some(100),
+            last_published: None,
+            last_finalized: 0
+        };
+
+        assert!(poller.poll().await.unwrap());
+        assert_eq!(poller.next_slot, Some(106));
+
+        let mut received = Vec::new();
+        while let Ok(msg) = rx.try_recv() {
+            received.push(match msg.update {
+                SourceUpdate::Block(block) => format!("block {}", block.meta.slot),
+                SourceUpdate::Slot(upd) => format!("{:?} {}", upd.status(), upd.slot),
+                upd => format!("{:?}", upd)
+            });
+        }
+        assert_eq!(received, [
+            "block 100",
+            "SlotConfirmed 100",
+            "block 102",
+            "SlotConfirmed 102",
+            "block 103",
+            "SlotConfirmed 103",
+            "block 105",
+            "SlotConfirmed 105",
+            "SlotFinalized 102"
+        ]);
+    }
+}
\ No newline at end of file
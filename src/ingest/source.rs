@@ -1,28 +1,115 @@
-use crate::data::ItemIndex;
+use super::fallback::{fallback_deactivated, SourceRole};
+use super::recorder::Recorder;
+use crate::data::{DataMessage, ItemIndex};
 use crate::geyser::api::subscribe_update::UpdateOneof;
-use crate::geyser::api::{CommitmentLevel, SubscribeRequest, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterTransactions, SubscribeUpdateBlockMeta, SubscribeUpdateTransaction};
+use crate::geyser::api::{CommitmentLevel, SubscribeRequest, SubscribeRequestFilterAccounts, SubscribeRequestFilterBlocks, SubscribeRequestFilterBlocksMeta, SubscribeRequestFilterSlots, SubscribeRequestPing, SubscribeRequestFilterTransactions, SubscribeUpdateAccountInfo, SubscribeUpdateBlock, SubscribeUpdateBlockMeta, SubscribeUpdateSlot, SubscribeUpdateTransaction};
 use crate::geyser::solana::storage::confirmed_block::{CompiledInstruction, MessageAddressTableLookup, MessageHeader, TransactionStatusMeta};
 use crate::geyser::GeyserClient;
+use crate::query::UpstreamFilter;
 use crate::Name;
-use anyhow::{anyhow, bail};
+use anyhow::bail;
 use std::collections::HashMap;
-use std::time::Duration;
-use tokio::time::timeout;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use tokio::select;
+use tokio::sync::watch;
+use tokio::time::{interval, sleep, Instant, MissedTickBehavior};
+use tokio_stream::wrappers::ReceiverStream;
 use tokio_stream::StreamExt;
-use tracing::{debug, error, info, instrument};
+use tracing::{debug, error, info, instrument, warn};
+
+
+#[derive(Debug, Clone)]
+pub struct SourceOptions {
+    /// Whether to ingest vote transactions
+    pub votes: bool,
+    /// Whether to ingest account updates
+    pub accounts: bool,
+    /// Whether to request only the data needed by active subscriptions
+    pub filter_pushdown: bool,
+    /// Interval between keep-alive pings (zero disables pings)
+    pub ping_interval: Duration,
+    /// Maximum time without any updates, after which the session is considered broken
+    pub idle_timeout: Duration,
+    /// File to record received updates to
+    pub record: Option<PathBuf>,
+    /// Whether to receive transactions individually or as parts of full blocks
+    pub mode: SourceMode,
+    /// Whether updates of the source are always used or only as a fallback
+    pub role: SourceRole
+}
+
+
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq)]
+pub enum SourceMode {
+    /// Subscribe to transaction and block meta updates
+    #[default]
+    Transactions,
+    /// Subscribe to block updates with transactions included
+    Blocks
+}
 
 
 #[derive(Debug)]
 pub struct SourceMessage {
     pub source: Name,
     pub update: SourceUpdate,
+    /// Local time of receipt
+    pub received_at: Instant
+}
+
+
+impl SourceMessage {
+    pub fn new(source: Name, update: SourceUpdate) -> Self {
+        Self {
+            source,
+            update,
+            received_at: Instant::now()
+        }
+    }
 }
 
 
 #[derive(Debug)]
 pub enum SourceUpdate {
-    Block(SubscribeUpdateBlockMeta),
-    Transaction(TransactionUpdate)
+    Block(BlockUpdate),
+    Transaction(TransactionUpdate),
+    Account(AccountUpdate),
+    Slot(SubscribeUpdateSlot),
+    /// Already mapped data, e.g. received from an upstream spray instance
+    Data(DataMessage),
+    /// Slots below the given one are closed and won't receive any more updates,
+    /// emitted by deduplication
+    SlotsClosed(u64)
+}
+
+
+#[derive(Debug)]
+pub struct BlockUpdate {
+    pub meta: SubscribeUpdateBlockMeta,
+    /// Number of transactions, that the source is expected to deliver for the block,
+    /// `None` when unknown
+    pub transaction_count: Option<u64>,
+    /// Whether all expected transactions were received, set during deduplication
+    pub complete: Option<bool>
+}
+
+
+impl BlockUpdate {
+    pub fn new(meta: SubscribeUpdateBlockMeta) -> Self {
+        Self {
+            transaction_count: Some(meta.executed_transaction_count),
+            meta,
+            complete: None
+        }
+    }
+}
+
+
+#[derive(Debug)]
+pub struct AccountUpdate {
+    pub slot: u64,
+    pub account: SubscribeUpdateAccountInfo
 }
 
 
@@ -30,6 +117,7 @@ pub enum SourceUpdate {
 pub struct TransactionUpdate {
     pub slot: u64,
     pub index: ItemIndex,
+    pub is_vote: bool,
     pub signatures: Vec<Vec<u8>>,
     pub header: MessageHeader,
     pub account_keys: Vec<Vec<u8>>,
@@ -66,6 +154,7 @@ impl TransactionUpdate {
         Ok(TransactionUpdate {
             slot: update.slot,
             index: upd.index as usize,
+            is_vote: upd.is_vote,
             signatures: tx.signatures,
             header,
             account_keys: message.account_keys,
@@ -83,126 +172,763 @@ impl TransactionUpdate {
 pub async fn source_loop(
     output: tokio::sync::mpsc::Sender<SourceMessage>,
     name: Name,
-    mut client: GeyserClient
+    mut client: GeyserClient,
+    options: SourceOptions,
+    mut filter: watch::Receiver<UpstreamFilter>,
+    mut fallback: Option<watch::Receiver<bool>>
 ) -> anyhow::Result<()> 
 {
-    let mut first_session = true;
-    let mut errors = 0;
-    let backoff_ms = [0, 0, 200, 500, 1000, 2000, 5000];
+    let recorder = options.record.as_deref().map(Recorder::create).transpose()?;
+    // lazy sources are connected in the middle of operation, where failures should not be fatal
+    let mut backoff = Backoff::new(fallback.is_none());
     while !output.is_closed() {
+        if let Some(active) = fallback.as_mut().filter(|active| !*active.borrow()) {
+            if active.wait_for(|active| *active).await.is_err() {
+                return Ok(())
+            }
+            info!("fallback is active, connecting");
+        }
         let mut update_received = false;
-        match source_session(
+        let session = source_session(
             &output,
             name,
             &mut client,
+            &options,
+            &mut filter,
+            recorder.as_ref(),
             &mut update_received
-        ).await {
-            Ok(_) => return Ok(()),
-            Err(err) => {
-                crate::metrics::register_data_source_error(name);
-                if first_session && !update_received {
-                    return Err(err)
-                } else {
-                    error!(err =? err, "data source failure");
-                }
-                first_session = false;
-                if update_received {
-                    errors = 1;
-                } else {
-                    errors += 1;
-                }
-                let pause = backoff_ms[errors.min(backoff_ms.len() - 1)];
-                if pause > 0 {
-                    info!("will pause data source for {} ms", pause);
-                    tokio::time::sleep(Duration::from_millis(pause)).await;
-                }
+        );
+        let result = select! {
+            result = session => result,
+            _ = fallback_deactivated(fallback.as_mut()) => {
+                info!("fallback is not active, disconnecting");
+                continue
             }
+        };
+        match result {
+            Ok(_) => return Ok(()),
+            Err(err) => backoff.failure(name, err, update_received).await?
         }
     }
     Ok(())
 }
 
 
+/// Reconnection policy shared by all source kinds
+pub struct Backoff {
+    first_session: bool,
+    errors: usize
+}
+
+
+impl Backoff {
+    /// When `fatal_first_failure` is set, failure of the first session
+    /// before any update was received terminates the source
+    pub fn new(fatal_first_failure: bool) -> Self {
+        Self {
+            first_session: fatal_first_failure,
+            errors: 0
+        }
+    }
+
+    /// Handles a session failure.
+    ///
+    /// Returns the error, when it should terminate the source,
+    /// otherwise pauses according to the number of consecutive failed sessions.
+    pub async fn failure(&mut self, name: Name, err: anyhow::Error, update_received: bool) -> anyhow::Result<()> {
+        const BACKOFF_MS: [u64; 7] = [0, 0, 200, 500, 1000, 2000, 5000];
+        crate::metrics::register_data_source_error(name);
+        if self.first_session && !update_received {
+            return Err(err)
+        } else {
+            error!(err =? err, "data source failure");
+        }
+        self.first_session = false;
+        if update_received {
+            self.errors = 1;
+        } else {
+            self.errors += 1;
+        }
+        let pause = BACKOFF_MS[self.errors.min(BACKOFF_MS.len() - 1)];
+        if pause > 0 {
+            info!("will pause data source for {} ms", pause);
+            tokio::time::sleep(Duration::from_millis(pause)).await;
+        }
+        Ok(())
+    }
+}
+
+
 async fn source_session(
     output: &tokio::sync::mpsc::Sender<SourceMessage>,
     name: Name,
     client: &mut GeyserClient,
+    options: &SourceOptions,
+    filter: &mut watch::Receiver<UpstreamFilter>,
+    recorder: Option<&Recorder>,
     update_received: &mut bool
 ) -> anyhow::Result<()>
 {
-    let req = SubscribeRequest {
-        transactions: HashMap::from([
-            (
-                "transactions".to_string(),
-                SubscribeRequestFilterTransactions {
-                    vote: Some(false),
-                    ..SubscribeRequestFilterTransactions::default()
-                }
-            )
-        ]),
-        blocks_meta: HashMap::from([
-            (
-                "blocks".to_string(),
-                SubscribeRequestFilterBlocksMeta::default()
-            )
-        ]),
-        commitment: Some(CommitmentLevel::Processed as i32),
-        ..SubscribeRequest::default()
-    };
+    let (req_tx, req_rx) = tokio::sync::mpsc::channel(1);
+    
+    let request = build_request(name, options, filter);
+    let mut narrowed = is_narrowed(&request);
+    req_tx.send(request).await?;
 
-    let mut updates = client.subscribe(tokio_stream::once(req))
+    let mut updates = client.subscribe(ReceiverStream::new(req_rx))
         .await?
         .into_inner();
 
     debug!("subscribed to updates");
     
-    while let Some(upd) = timeout(Duration::from_secs(30), updates.try_next())
-        .await
-        .map_err(|_| anyhow!("haven't received updates for more than 30 seconds"))?? 
-    {
+    let mut pings = interval(options.ping_interval.max(Duration::from_millis(1)));
+    pings.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    pings.reset();
+    let mut ping = Ping::default();
+    let mut vote_filter = VoteFilter::default();
+    
+    let idle = sleep(options.idle_timeout);
+    tokio::pin!(idle);
+    
+    loop {
+        let mut upd = select! {
+            upd = updates.try_next() => {
+                match upd? {
+                    Some(upd) => upd,
+                    None => break
+                }
+            },
+            _ = &mut idle => {
+                bail!("haven't received updates for more than {} seconds", options.idle_timeout.as_secs())
+            },
+            _ = pings.tick(), if !options.ping_interval.is_zero() => {
+                req_tx.send(ping.request()).await?;
+                continue
+            },
+            Ok(_) = filter.changed(), if options.filter_pushdown => {
+                debug!("updating upstream filter");
+                let request = build_request(name, options, filter);
+                narrowed = is_narrowed(&request);
+                req_tx.send(request).await?;
+                continue
+            }
+        };
+        
+        if let Some(created_at) = upd.created_at.and_then(|ts| SystemTime::try_from(ts).ok()) {
+            let latency = SystemTime::now().duration_since(created_at).unwrap_or_default();
+            crate::metrics::register_source_latency(name, latency);
+        }
+        
+        if let Some(recorder) = recorder {
+            recorder.record(&mut upd).await;
+        }
+        
         if let Some(upd) = upd.update_oneof {
-            let update = match upd {
-                UpdateOneof::Transaction(tx) => {
-                    match TransactionUpdate::from_subscription_update(tx) {
-                        Ok(tx) => {
-                            debug!(
-                                slot = tx.slot,
-                                transaction_index = tx.index,
-                                "received"
-                            );
-                            SourceUpdate::Transaction(tx)
-                        },
-                        Err(missing_field) => {
-                            bail!("got transaction update with missing {} field", missing_field)
-                        },
-                    }
+            let updates = match upd {
+                UpdateOneof::Ping(_) => {
+                    // respond to keep the stream alive behind load balancers
+                    req_tx.send(Ping::reply()).await?;
+                    continue
                 },
-                UpdateOneof::BlockMeta(block) => {
-                    debug!(
-                        slot = block.slot,
-                        block_time =% chrono::DateTime::from_timestamp(
-                            block.block_time.map_or(0, |t| t.timestamp), 
-                            0
-                        ).unwrap(),
-                        "published"
-                    );
-                    SourceUpdate::Block(block)
+                UpdateOneof::Pong(pong) => {
+                    if let Some(rtt) = ping.pong(pong.id) {
+                        debug!(rtt_ms = rtt.as_millis(), "pong");
+                        crate::metrics::register_ping_rtt(name, rtt);
+                    }
+                    continue
                 },
-                _ => continue
+                upd => match convert_update(upd) {
+                    Ok(updates) => updates,
+                    Err(err) => {
+                        crate::metrics::register_data_source_error(name);
+                        error!(err =? err, "skipping malformed update");
+                        continue
+                    }
+                }
             };
             
+            // keep-alive messages don't prove, that the source is still delivering data
+            idle.as_mut().reset(Instant::now() + options.idle_timeout);
             *update_received = true;
             
-            let msg = SourceMessage {
-                source: name,
-                update
-            };
-            
-            if output.send(msg).await.is_err() {
-                return Ok(())
+            for mut update in updates {
+                if !options.votes {
+                    // vote transactions are not filtered upstream in the full-block mode
+                    if !vote_filter.accept(&mut update) {
+                        continue
+                    }
+                    if let (SourceMode::Transactions, SourceUpdate::Block(block)) = (options.mode, &mut update) {
+                        // votes were filtered upstream, their number is unknown
+                        block.transaction_count = None;
+                    }
+                }
+                if let (true, SourceUpdate::Block(block)) = (narrowed, &mut update) {
+                    // only a subset of transactions was requested
+                    block.transaction_count = None;
+                }
+                
+                let msg = SourceMessage::new(name, update);
+                
+                if output.send(msg).await.is_err() {
+                    return Ok(())
+                }
             }
         }
     }
 
     bail!("unexpected end of update stream")
+}
+
+
+/// Converts a geyser update into source updates.
+///
+/// Full blocks are exploded into transactions ordered by index, followed by the block itself.
+/// Updates, that are not relevant for ingestion, produce nothing.
+pub fn convert_update(upd: UpdateOneof) -> anyhow::Result<Vec<SourceUpdate>> {
+    let update = match upd {
+        UpdateOneof::Transaction(tx) => {
+            match TransactionUpdate::from_subscription_update(tx) {
+                Ok(tx) => {
+                    debug!(
+                        slot = tx.slot,
+                        transaction_index = tx.index,
+                        "received"
+                    );
+                    SourceUpdate::Transaction(tx)
+                },
+                Err(missing_field) => {
+                    bail!("got transaction update with missing {} field", missing_field)
+                },
+            }
+        },
+        UpdateOneof::BlockMeta(block) => {
+            debug!(
+                slot = block.slot,
+                block_time =% chrono::DateTime::from_timestamp(
+                    block.block_time.map_or(0, |t| t.timestamp), 
+                    0
+                ).unwrap(),
+                "published"
+            );
+            SourceUpdate::Block(BlockUpdate::new(block))
+        },
+        UpdateOneof::Block(block) => return explode_block(block),
+        UpdateOneof::Account(acc) => {
+            if acc.is_startup {
+                return Ok(Vec::new())
+            }
+            let Some(account) = acc.account else {
+                bail!("got account update with missing .account field")
+            };
+            debug!(
+                slot = acc.slot,
+                write_version = account.write_version,
+                "received"
+            );
+            SourceUpdate::Account(AccountUpdate {
+                slot: acc.slot,
+                account
+            })
+        },
+        UpdateOneof::Slot(slot) => {
+            debug!(
+                slot = slot.slot,
+                status = slot.status().as_str_name(),
+                "received"
+            );
+            SourceUpdate::Slot(slot)
+        },
+        _ => return Ok(Vec::new())
+    };
+    Ok(vec![update])
+}
+
+
+fn explode_block(mut block: SubscribeUpdateBlock) -> anyhow::Result<Vec<SourceUpdate>> {
+    debug!(
+        slot = block.slot,
+        transactions = block.transactions.len(),
+        "received"
+    );
+
+    block.transactions.sort_by_key(|tx| tx.index);
+
+    let mut updates = Vec::with_capacity(block.transactions.len() + 1);
+
+    for tx in std::mem::take(&mut block.transactions) {
+        let tx = TransactionUpdate::from_subscription_update(SubscribeUpdateTransaction {
+            transaction: Some(tx),
+            slot: block.slot
+        });
+        match tx {
+            Ok(tx) => updates.push(SourceUpdate::Transaction(tx)),
+            Err(missing_field) => {
+                bail!("got block update with missing transaction {} field", missing_field)
+            }
+        }
+    }
+
+    updates.push(SourceUpdate::Block(BlockUpdate::new(SubscribeUpdateBlockMeta {
+        slot: block.slot,
+        blockhash: block.blockhash,
+        rewards: block.rewards,
+        block_time: block.block_time,
+        block_height: block.block_height,
+        parent_slot: block.parent_slot,
+        parent_blockhash: block.parent_blockhash,
+        executed_transaction_count: block.executed_transaction_count,
+        entries_count: block.entries_count
+    })));
+
+    Ok(updates)
+}
+
+
+/// Drops vote transactions and excludes them from
+/// the expected transaction count of the corresponding block.
+#[derive(Default)]
+pub struct VoteFilter {
+    slot: u64,
+    dropped: u64
+}
+
+
+impl VoteFilter {
+    /// Returns `false` when the update should be dropped
+    pub fn accept(&mut self, update: &mut SourceUpdate) -> bool {
+        match update {
+            SourceUpdate::Transaction(tx) if tx.is_vote => {
+                if tx.slot != self.slot {
+                    self.slot = tx.slot;
+                    self.dropped = 0;
+                }
+                self.dropped += 1;
+                false
+            },
+            SourceUpdate::Block(block) => {
+                if block.meta.slot == self.slot {
+                    block.transaction_count = block.transaction_count.map(|count| {
+                        count.saturating_sub(self.dropped)
+                    });
+                }
+                true
+            },
+            _ => true
+        }
+    }
+}
+
+
+/// Id of pings, that answer server pings and are not measured
+const PING_REPLY_ID: i32 = 0;
+
+
+#[derive(Default)]
+struct Ping {
+    id: i32,
+    sent_at: Option<Instant>
+}
+
+
+impl Ping {
+    fn request(&mut self) -> SubscribeRequest {
+        // skips the reply id on wrap around
+        self.id = self.id % i32::MAX + 1;
+        self.sent_at = Some(Instant::now());
+        Self::with_id(self.id)
+    }
+
+    /// Answers a server ping without affecting the round-trip measurement
+    fn reply() -> SubscribeRequest {
+        Self::with_id(PING_REPLY_ID)
+    }
+
+    fn with_id(id: i32) -> SubscribeRequest {
+        SubscribeRequest {
+            ping: Some(SubscribeRequestPing {
+                id
+            }),
+            ..SubscribeRequest::default()
+        }
+    }
+
+    /// Returns the round-trip time, if the pong corresponds to the last sent ping
+    fn pong(&mut self, id: i32) -> Option<Duration> {
+        if id != self.id {
+            return None
+        }
+        self.sent_at.take().map(|sent_at| sent_at.elapsed())
+    }
+}
+
+
+/// Whether the request selects only a part of (non-vote) block transactions
+fn is_narrowed(request: &SubscribeRequest) -> bool {
+    match request.blocks.get("blocks") {
+        Some(blocks) => blocks.include_transactions != Some(true) || !blocks.account_include.is_empty(),
+        None => !request.transactions.get("transactions").is_some_and(|f| f.account_include.is_empty())
+    }
+}
+
+
+/// Maximum number of accounts in a pushed down filter
+const MAX_PUSHDOWN_ACCOUNTS: usize = 1000;
+
+
+/// Maximum number of pushed down signatures, each of which takes a separate transaction filter
+const MAX_PUSHDOWN_SIGNATURES: usize = 10;
+
+
+/// Replaces filters, that upstream servers would likely reject as too large, with requests of all data
+fn limit_filter(name: Name, options: &SourceOptions, filter: &mut UpstreamFilter) {
+    let too_many_transaction_filters = filter.transaction_accounts.len() > MAX_PUSHDOWN_ACCOUNTS
+        || filter.signatures.len() > MAX_PUSHDOWN_SIGNATURES;
+    if !filter.all_transactions && too_many_transaction_filters {
+        warn!(
+            accounts = filter.transaction_accounts.len(),
+            signatures = filter.signatures.len(),
+            "transaction filter is too large, requesting all transactions"
+        );
+        crate::metrics::register_pushdown_fallback(name);
+        filter.all_transactions = true;
+    }
+    let too_many_accounts = filter.account_pubkeys.len() > MAX_PUSHDOWN_ACCOUNTS
+        || filter.account_owners.len() > MAX_PUSHDOWN_ACCOUNTS;
+    if options.accounts && !filter.all_accounts && too_many_accounts {
+        warn!(
+            pubkeys = filter.account_pubkeys.len(),
+            owners = filter.account_owners.len(),
+            "account filter is too large, requesting all accounts"
+        );
+        crate::metrics::register_pushdown_fallback(name);
+        filter.all_accounts = true;
+    }
+}
+
+
+fn build_request(name: Name, options: &SourceOptions, filter: &mut watch::Receiver<UpstreamFilter>) -> SubscribeRequest {
+    let mut filter = options.filter_pushdown.then(|| filter.borrow_and_update().clone());
+    if let Some(filter) = filter.as_mut() {
+        limit_filter(name, options, filter);
+    }
+    
+    let transaction_filter = |account_include: Vec<String>| SubscribeRequestFilterTransactions {
+        vote: if options.votes { None } else { Some(false) },
+        account_include,
+        ..SubscribeRequestFilterTransactions::default()
+    };
+    
+    // `None` means, that no transactions are required
+    let transaction_accounts = match filter.as_ref() {
+        Some(f) if !f.all_transactions => {
+            if f.transaction_accounts.is_empty() {
+                None
+            } else {
+                Some(f.transaction_accounts.iter().cloned().collect())
+            }
+        },
+        _ => Some(Vec::new())
+    };
+
+    let signatures: Vec<String> = match filter.as_ref() {
+        Some(f) if !f.all_transactions => f.signatures.iter().cloned().collect(),
+        _ => Vec::new()
+    };
+    
+    let mut transactions = HashMap::new();
+    let mut blocks = HashMap::new();
+    let mut blocks_meta = HashMap::new();
+    match options.mode {
+        SourceMode::Transactions => {
+            if let Some(account_include) = transaction_accounts {
+                transactions.insert("transactions".to_string(), transaction_filter(account_include));
+            }
+            // a transaction filter matches a single signature
+            for (i, signature) in signatures.into_iter().enumerate() {
+                transactions.insert(format!("signature_{}", i), SubscribeRequestFilterTransactions {
+                    signature: Some(signature),
+                    ..transaction_filter(Vec::new())
+                });
+            }
+            blocks_meta.insert("blocks".to_string(), SubscribeRequestFilterBlocksMeta::default());
+        },
+        SourceMode::Blocks => {
+            // blocks can't be filtered by signature, so all transactions are required
+            let transaction_accounts = if signatures.is_empty() {
+                transaction_accounts
+            } else {
+                Some(Vec::new())
+            };
+            blocks.insert("blocks".to_string(), SubscribeRequestFilterBlocks {
+                include_transactions: Some(transaction_accounts.is_some()),
+                account_include: transaction_accounts.unwrap_or_default(),
+                include_accounts: Some(false),
+                include_entries: Some(false)
+            });
+        }
+    }
+    
+    let mut accounts = HashMap::new();
+    if options.accounts {
+        match filter.as_ref() {
+            Some(f) if !f.all_accounts => {
+                // account and owner lists of a single filter are combined with AND
+                if !f.account_pubkeys.is_empty() {
+                    accounts.insert("account_pubkeys".to_string(), SubscribeRequestFilterAccounts {
+                        account: f.account_pubkeys.iter().cloned().collect(),
+                        ..SubscribeRequestFilterAccounts::default()
+                    });
+                }
+                if !f.account_owners.is_empty() {
+                    accounts.insert("account_owners".to_string(), SubscribeRequestFilterAccounts {
+                        owner: f.account_owners.iter().cloned().collect(),
+                        ..SubscribeRequestFilterAccounts::default()
+                    });
+                }
+            },
+            _ => {
+                accounts.insert("accounts".to_string(), SubscribeRequestFilterAccounts::default());
+            }
+        }
+    }
+    
+    SubscribeRequest {
+        transactions,
+        slots: HashMap::from([
+            (
+                "slots".to_string(),
+                SubscribeRequestFilterSlots {
+                    filter_by_commitment: Some(false),
+                    interslot_updates: Some(true)
+                }
+            )
+        ]),
+        blocks,
+        blocks_meta,
+        accounts,
+        commitment: Some(CommitmentLevel::Processed as i32),
+        ..SubscribeRequest::default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geyser::connect_mock_geyser;
+    use tokio::time::timeout;
+
+    fn describe(update: &SourceUpdate) -> String {
+        match update {
+            SourceUpdate::Slot(upd) => format!("slot {}", upd.slot),
+            SourceUpdate::Transaction(tx) => format!("tx {}:{}", tx.slot, tx.index),
+            SourceUpdate::Block(block) => format!("block {}", block.meta.slot),
+            upd => format!("{:?}", upd)
+        }
+    }
+
+    fn options(mode: SourceMode, filter_pushdown: bool) -> SourceOptions {
+        SourceOptions {
+            votes: false,
+            accounts: false,
+            filter_pushdown,
+            ping_interval: Duration::from_millis(100),
+            idle_timeout: Duration::from_secs(10),
+            record: None,
+            mode,
+            role: SourceRole::Primary
+        }
+    }
+
+    /// Runs a source against the mock script and returns the first `count` received updates
+    async fn receive(script: &str, idle_timeout: Duration, count: usize) -> Vec<String> {
+        let client = connect_mock_geyser(script).await;
+        let options = SourceOptions {
+            idle_timeout,
+            ..options(SourceMode::Transactions, false)
+        };
+        let (_filter_tx, filter_rx) = watch::channel(UpstreamFilter::default());
+        let (tx, mut rx) = tokio::sync::mpsc::channel(100);
+        let source = tokio::spawn(source_loop(tx, "mock", client, options, filter_rx, None));
+
+        let mut received = Vec::new();
+        while received.len() < count {
+            let msg = timeout(Duration::from_secs(5), rx.recv()).await
+                .expect("source didn't deliver expected updates")
+                .unwrap();
+            received.push(describe(&msg.update));
+        }
+        source.abort();
+        received
+    }
+
+    #[tokio::test]
+    async fn reconnect_after_disconnect() {
+        let script = r#"
+            sessions:
+              - - slot: { slot: 10, parent: 9 }
+                - transaction: { slot: 10, index: 0 }
+                - disconnect
+              - - transaction: { slot: 10, index: 1 }
+                - block: { slot: 10, executed_transaction_count: 2 }
+        "#;
+        assert_eq!(
+            receive(script, Duration::from_secs(10), 4).await,
+            ["slot 10", "tx 10:0", "tx 10:1", "block 10"]
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_after_stall() {
+        let script = r#"
+            sessions:
+              - - slot: { slot: 10, parent: 9 }
+                - stall: 2
+                - transaction: { slot: 10, index: 0 }
+              - - transaction: { slot: 10, index: 1 }
+        "#;
+        assert_eq!(
+            receive(script, Duration::from_millis(500), 2).await,
+            ["slot 10", "tx 10:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn idle_timeout_after_sleep_with_pings() {
+        let script = r#"
+            sessions:
+              - - slot: { slot: 10, parent: 9 }
+                - sleep: 2
+                - transaction: { slot: 10, index: 0 }
+              - - transaction: { slot: 10, index: 1 }
+        "#;
+        assert_eq!(
+            receive(script, Duration::from_millis(500), 2).await,
+            ["slot 10", "tx 10:1"]
+        );
+    }
+
+    #[tokio::test]
+    async fn skip_malformed_transaction() {
+        let script = r#"
+            sessions:
+              - - slot: { slot: 10, parent: 9 }
+                - transaction: { slot: 10, index: 0 }
+                - malformed_transaction: { slot: 10, index: 1 }
+                - transaction: { slot: 10, index: 2 }
+                - block: { slot: 10, executed_transaction_count: 3 }
+              - - slot: { slot: 11, parent: 10 }
+        "#;
+        assert_eq!(
+            receive(script, Duration::from_secs(10), 4).await,
+            ["slot 10", "tx 10:0", "tx 10:2", "block 10"]
+        );
+    }
+
+    #[test]
+    fn oversized_filters() {
+        let request = |filter: UpstreamFilter| {
+            let (_tx, mut rx) = watch::channel(filter);
+            let options = SourceOptions {
+                accounts: true,
+                ..options(SourceMode::Transactions, true)
+            };
+            build_request("test", &options, &mut rx)
+        };
+        let accounts = |n: usize| (0..n).map(|i| i.to_string()).collect();
+
+        let within_limits = request(UpstreamFilter {
+            transaction_accounts: accounts(MAX_PUSHDOWN_ACCOUNTS),
+            signatures: accounts(MAX_PUSHDOWN_SIGNATURES),
+            account_pubkeys: accounts(MAX_PUSHDOWN_ACCOUNTS),
+            ..UpstreamFilter::default()
+        });
+        assert_eq!(within_limits.transactions.len(), MAX_PUSHDOWN_SIGNATURES + 1);
+        assert_eq!(within_limits.transactions["transactions"].account_include.len(), MAX_PUSHDOWN_ACCOUNTS);
+        assert_eq!(within_limits.accounts["account_pubkeys"].account.len(), MAX_PUSHDOWN_ACCOUNTS);
+
+        for filter in [
+            UpstreamFilter {
+                transaction_accounts: accounts(MAX_PUSHDOWN_ACCOUNTS + 1),
+                ..UpstreamFilter::default()
+            },
+            UpstreamFilter {
+                signatures: accounts(MAX_PUSHDOWN_SIGNATURES + 1),
+                ..UpstreamFilter::default()
+            }
+        ] {
+            let request = request(filter);
+            assert_eq!(request.transactions.len(), 1);
+            assert!(request.transactions["transactions"].account_include.is_empty());
+            assert!(!is_narrowed(&request));
+        }
+
+        let request = request(UpstreamFilter {
+            account_owners: accounts(MAX_PUSHDOWN_ACCOUNTS + 1),
+            ..UpstreamFilter::default()
+        });
+        assert_eq!(request.accounts.len(), 1);
+        assert_eq!(request.accounts["accounts"], SubscribeRequestFilterAccounts::default());
+    }
+
+    #[test]
+    fn ping_reply_between_ping_and_pong() {
+        let mut ping = Ping::default();
+        let id = ping.request().ping.unwrap().id;
+        let reply_id = Ping::reply().ping.unwrap().id;
+        assert_ne!(id, reply_id);
+        assert!(ping.pong(reply_id).is_none());
+        assert!(ping.pong(id).is_some());
+        assert!(ping.pong(id).is_none());
+
+        ping.id = i32::MAX;
+        assert_eq!(ping.request().ping.unwrap().id, 1);
+    }
+
+    #[test]
+    fn narrowed_requests() {
+        let narrowed = |mode, filter: Option<UpstreamFilter>| {
+            let (_tx, mut rx) = watch::channel(filter.clone().unwrap_or_default());
+            is_narrowed(&build_request("test", &options(mode, filter.is_some()), &mut rx))
+        };
+        let all = UpstreamFilter {
+            all_transactions: true,
+            ..UpstreamFilter::default()
+        };
+        let some = UpstreamFilter {
+            transaction_accounts: ["11111111111111111111111111111111".to_string()].into(),
+            ..UpstreamFilter::default()
+        };
+        let none = UpstreamFilter::default();
+        let signatures = UpstreamFilter {
+            signatures: ["5VERv8NMvzbJMEkV8xnrLkEaWRtSz9CosKDYjCJjBRnbJLgp8uirBgmQpjKhoR4tjF3ZpRzrFmBV6UjKdiSZkQUW".to_string()].into(),
+            ..UpstreamFilter::default()
+        };
+
+        for mode in [SourceMode::Transactions, SourceMode::Blocks] {
+            assert!(!narrowed(mode, None));
+            assert!(!narrowed(mode, Some(all.clone())));
+            assert!(narrowed(mode, Some(some.clone())));
+            assert!(narrowed(mode, Some(none.clone())));
+        }
+        assert!(narrowed(SourceMode::Transactions, Some(signatures.clone())));
+        assert!(!narrowed(SourceMode::Blocks, Some(signatures.clone())));
+    }
+
+    #[test]
+    fn signature_filters() {
+        let filter = UpstreamFilter {
+            transaction_accounts: ["11111111111111111111111111111111".to_string()].into(),
+            signatures: ["sig1".to_string(), "sig2".to_string()].into(),
+            ..UpstreamFilter::default()
+        };
+        let (_tx, mut rx) = watch::channel(filter);
+        let request = build_request("test", &options(SourceMode::Transactions, true), &mut rx);
+        let mut keys: Vec<_> = request.transactions.keys().map(String::as_str).collect();
+        keys.sort();
+        assert_eq!(keys, ["signature_0", "signature_1", "transactions"]);
+        assert_eq!(request.transactions["signature_0"].signature.as_deref(), Some("sig1"));
+        assert!(request.transactions["signature_0"].account_include.is_empty());
+        assert_eq!(request.transactions["signature_1"].signature.as_deref(), Some("sig2"));
+        assert_eq!(request.transactions["transactions"].signature, None);
+    }
 }
\ No newline at end of file
@@ -0,0 +1,184 @@
+use super::model;
+use crate::data::{AccountData, AccountIndex, AccountList, Balance, BlockData, DataMessage, Instruction, LogMessage, Reward, RollbackData, SlotStatusData, TokenBalance, Transaction, TransactionData, TransactionVersion};
+use anyhow::{anyhow, bail, ensure, Context};
+use std::collections::HashMap;
+
+
+pub fn map_notification(notification: model::Notification) -> anyhow::Result<DataMessage> {
+    let msg = match notification {
+        model::Notification::Block(block) => DataMessage::Block(map_block(block)),
+        model::Notification::Transaction(tx) => DataMessage::Transaction(map_transaction(*tx)?),
+        model::Notification::Account(acc) => DataMessage::Account(map_account(acc)?),
+        model::Notification::Rollback { slots } => DataMessage::Rollback(RollbackData {
+            slots
+        }),
+        model::Notification::Commitment { slot, status } => DataMessage::SlotStatus(SlotStatusData {
+            slot,
+            status
+        }),
+        model::Notification::Gap { .. } => bail!("gap notification doesn't carry data")
+    };
+    Ok(msg)
+}
+
+
+fn map_block(block: model::Block) -> BlockData {
+    BlockData {
+        slot: block.slot,
+        hash: block.header.hash,
+        parent_slot: block.parent_slot,
+        parent_hash: block.parent_hash,
+        height: block.header.height,
+        timestamp: block.header.timestamp,
+        rewards: block.rewards.into_iter().map(|r| Reward {
+            pubkey: r.pubkey,
+            lamports: r.lamports,
+            post_balance: r.post_balance,
+            reward_type: r.reward_type,
+            commission: r.commission
+        }).collect(),
+        complete: block.header.complete
+    }
+}
+
+
+fn map_account(msg: model::AccountMessage) -> anyhow::Result<AccountData> {
+    let account = msg.account;
+    Ok(AccountData {
+        slot: msg.slot,
+        pubkey: account.pubkey,
+        lamports: account.lamports,
+        owner: account.owner,
+        executable: account.executable,
+        rent_epoch: account.rent_epoch,
+        data: decode_hex(&account.data).context("invalid account data")?,
+        write_version: account.write_version,
+        txn_signature: account.txn_signature
+    })
+}
+
+
+fn map_transaction(msg: model::TransactionMessage) -> anyhow::Result<TransactionData> {
+    let tx = msg.transaction.ok_or_else(|| anyhow!("transaction is missing"))?;
+
+    let version = match &tx.version {
+        serde_json::Value::String(s) if s == "legacy" => TransactionVersion::Legacy,
+        v => TransactionVersion::Other(
+            v.as_u64()
+                .and_then(|v| u8::try_from(v).ok())
+                .ok_or_else(|| anyhow!("invalid transaction version - {}", v))?
+        )
+    };
+
+    let accounts: AccountList = tx.account_keys.iter()
+        .chain(tx.loaded_addresses.writable.iter())
+        .chain(tx.loaded_addresses.readonly.iter())
+        .cloned()
+        .collect();
+
+    let loaded_addresses = serde_json::json!({
+        "writable": tx.loaded_addresses.writable,
+        "readonly": tx.loaded_addresses.readonly
+    }).to_string();
+
+    let account_index: HashMap<&str, AccountIndex> = {
+        let mut index = HashMap::with_capacity(accounts.len());
+        for (i, acc) in accounts.iter().enumerate().rev() {
+            index.insert(acc.as_str(), AccountIndex::try_from(i).context("too many accounts")?);
+        }
+        index
+    };
+
+    let lookup = |acc: &str| -> anyhow::Result<AccountIndex> {
+        account_index.get(acc).copied().ok_or_else(|| {
+            anyhow!("account {} is not listed in the transaction", acc)
+        })
+    };
+
+    let instructions = msg.instructions.into_iter().map(|ins| {
+        ensure!(!ins.instruction_address.is_empty(), "instruction address is empty");
+        Ok(Instruction {
+            program_id: lookup(&ins.program_id)?,
+            accounts: ins.accounts.iter().map(|acc| lookup(acc)).collect::<anyhow::Result<_>>()?,
+            binary_data: bs58::decode(&ins.data).into_vec().context("invalid instruction data")?,
+            instruction_address: ins.instruction_address,
+            data: ins.data,
+            error: ins.error,
+            compute_units_consumed: ins.compute_units_consumed,
+            is_committed: ins.is_committed,
+            has_dropped_log_messages: ins.has_dropped_log_messages,
+            account_list: accounts.clone()
+        })
+    }).collect::<anyhow::Result<Vec<_>>>()?;
+
+    // logs of instructions, that were not relayed, are dropped
+    let logs = msg.logs.into_iter().filter_map(|log| {
+        let instruction_index = instructions.iter().position(|ins| {
+            ins.instruction_address == log.instruction_address
+        })?;
+        Some(LogMessage {
+            log_index: log.log_index,
+            instruction_index,
+            instruction_address: log.instruction_address,
+            program_id: log.program_id,
+            kind: log.kind,
+            message: log.message
+        })
+    }).collect();
+
+    let balances = msg.balances.into_iter().map(|b| Balance {
+        account: b.account,
+        pre: b.pre,
+        post: b.post
+    }).collect();
+
+    let token_balances = msg.token_balances.into_iter().map(|b| TokenBalance {
+        account: b.account,
+        pre_mint: b.pre_mint,
+        post_mint: b.post_mint,
+        pre_decimals: b.pre_decimals,
+        post_decimals: b.post_decimals,
+        pre_program_id: b.pre_program_id,
+        post_program_id: b.post_program_id,
+        pre_owner: b.pre_owner,
+        post_owner: b.post_owner,
+        pre_amount: b.pre_amount,
+        post_amount: b.post_amount
+    }).collect();
+
+    Ok(TransactionData {
+        slot: msg.slot,
+        transaction_index: msg.transaction_index,
+        transaction: Transaction {
+            is_vote: tx.is_vote,
+            version,
+            account_keys: tx.account_keys.len(),
+            address_table_lookups: tx.address_table_lookups.to_string(),
+            num_readonly_signed_accounts: tx.num_readonly_signed_accounts,
+            num_readonly_unsigned_accounts: tx.num_readonly_unsigned_accounts,
+            num_required_signatures: tx.num_required_signatures,
+            recent_blockhash: tx.recent_blockhash,
+            signature: tx.signatures.get(0).and_then(|sig| sig.as_str()).map(str::to_string),
+            signatures: tx.signatures.to_string(),
+            err: tx.err.map(|err| err.to_string()),
+            compute_units_consumed: tx.compute_units_consumed,
+            fee: tx.fee,
+            loaded_addresses,
+            has_dropped_log_messages: tx.has_dropped_log_messages
+        },
+        instructions,
+        balances,
+        token_balances,
+        logs,
+        accounts
+    })
+}
+
+
+fn decode_hex(s: &str) -> anyhow::Result<Vec<u8>> {
+    let hex = s.strip_prefix("0x").ok_or_else(|| anyhow!("missing 0x prefix"))?;
+    ensure!(hex.len() % 2 == 0, "odd number of hex digits");
+    let mut bytes = vec![0; hex.len() / 2];
+    faster_hex::hex_decode(hex.as_bytes(), &mut bytes)?;
+    Ok(bytes)
+}
\ No newline at end of file
@@ -0,0 +1,6 @@
+mod mapping;
+mod model;
+mod source;
+
+
+pub use source::*;
\ No newline at end of file
@@ -0,0 +1,194 @@
+use crate::data::{Commitment, LogKind, RewardType};
+use serde::Deserialize;
+use serde_json::Value;
+use serde_with::{serde_as, DisplayFromStr};
+
+
+/// Payload of a `sprayNotification`
+#[derive(Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Notification {
+    Block(Block),
+    Transaction(Box<TransactionMessage>),
+    Account(AccountMessage),
+    Rollback {
+        slots: Vec<u64>
+    },
+    Commitment {
+        slot: u64,
+        status: Commitment
+    },
+    #[serde(rename_all = "camelCase")]
+    Gap {
+        skipped: u64,
+        from_slot: u64,
+        to_slot: u64
+    }
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Block {
+    pub slot: u64,
+    pub parent_slot: u64,
+    pub parent_hash: String,
+    pub header: BlockHeader,
+    #[serde(default)]
+    pub rewards: Vec<Reward>
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct BlockHeader {
+    pub hash: String,
+    pub height: Option<u64>,
+    pub timestamp: i64,
+    pub complete: Option<bool>
+}
+
+
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Reward {
+    pub pubkey: String,
+    #[serde_as(as = "DisplayFromStr")]
+    pub lamports: i64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub post_balance: u64,
+    pub reward_type: Option<RewardType>,
+    pub commission: Option<u8>
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TransactionMessage {
+    pub slot: u64,
+    pub transaction_index: usize,
+    pub transaction: Option<Transaction>,
+    #[serde(default)]
+    pub instructions: Vec<Instruction>,
+    #[serde(default)]
+    pub balances: Vec<Balance>,
+    #[serde(default)]
+    pub token_balances: Vec<TokenBalance>,
+    #[serde(default)]
+    pub logs: Vec<Log>
+}
+
+
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Transaction {
+    /// Either `"legacy"` or a version number
+    pub version: Value,
+    pub account_keys: Vec<String>,
+    pub address_table_lookups: Value,
+    pub num_readonly_signed_accounts: u8,
+    pub num_readonly_unsigned_accounts: u8,
+    pub num_required_signatures: u8,
+    pub recent_blockhash: String,
+    pub signatures: Value,
+    pub err: Option<Value>,
+    #[serde_as(as = "DisplayFromStr")]
+    pub fee: u64,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub compute_units_consumed: Option<u64>,
+    pub loaded_addresses: LoadedAddresses,
+    pub has_dropped_log_messages: bool,
+    pub is_vote: bool
+}
+
+
+#[derive(Deserialize)]
+pub struct LoadedAddresses {
+    pub writable: Vec<String>,
+    pub readonly: Vec<String>
+}
+
+
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Instruction {
+    pub instruction_address: Vec<usize>,
+    pub program_id: String,
+    pub accounts: Vec<String>,
+    pub data: String,
+    pub error: Option<String>,
+    #[serde_as(as = "Option<DisplayFromStr>")]
+    pub compute_units_consumed: Option<u64>,
+    pub is_committed: bool,
+    pub has_dropped_log_messages: bool
+}
+
+
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Balance {
+    pub account: String,
+    #[serde_as(as = "DisplayFromStr")]
+    pub pre: u64,
+    #[serde_as(as = "DisplayFromStr")]
+    pub post: u64
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TokenBalance {
+    pub account: String,
+    pub pre_mint: Option<String>,
+    pub post_mint: Option<String>,
+    pub pre_decimals: Option<u32>,
+    pub post_decimals: Option<u32>,
+    pub pre_program_id: Option<String>,
+    pub post_program_id: Option<String>,
+    pub pre_owner: Option<String>,
+    pub post_owner: Option<String>,
+    pub pre_amount: Option<String>,
+    pub post_amount: Option<String>
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Log {
+    pub log_index: usize,
+    pub instruction_address: Vec<usize>,
+    pub program_id: String,
+    pub kind: LogKind,
+    pub message: String
+}
+
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AccountMessage {
+    pub slot: u64,
+    pub account: Account
+}
+
+
+#[serde_as]
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Account {
+    pub pubkey: String,
+    #[serde_as(as = "DisplayFromStr")]
+    pub lamports: u64,
+    pub owner: String,
+    pub executable: bool,
+    #[serde_as(as = "DisplayFromStr")]
+    pub rent_epoch: u64,
+    /// Hex encoded data with the `0x` prefix
+    pub data: String,
+    #[serde_as(as = "DisplayFromStr")]
+    pub write_version: u64,
+    pub txn_signature: Option<String>
+}
\ No newline at end of file
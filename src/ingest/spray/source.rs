@@ -0,0 +1,172 @@
+use super::mapping::map_notification;
+use super::model::Notification;
+use crate::data::Commitment;
+use crate::ingest::source::{Backoff, SourceMessage, SourceUpdate};
+use crate::query::{FieldSelection, SolanaQuery};
+use crate::Name;
+use anyhow::{bail, Context};
+use futures_util::{SinkExt, StreamExt};
+use serde::Deserialize;
+use serde_json::json;
+use std::time::Duration;
+use tokio::select;
+use tokio::sync::mpsc::Sender;
+use tokio::time::{sleep, Instant};
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, error, instrument};
+
+
+#[derive(Debug, Clone)]
+pub struct SprayOptions {
+    /// WebSocket endpoint of the upstream spray instance
+    pub url: String,
+    /// Query selecting the relayed data
+    pub query: SolanaQuery,
+    /// Maximum time without any notifications, after which the session is considered broken
+    pub idle_timeout: Duration
+}
+
+
+#[derive(Deserialize)]
+struct ServerMessage {
+    params: Option<NotificationParams>,
+    error: Option<ErrorObject>
+}
+
+
+#[derive(Deserialize)]
+struct NotificationParams {
+    result: Option<Notification>
+}
+
+
+#[derive(Deserialize)]
+struct ErrorObject {
+    code: i64,
+    message: String
+}
+
+
+#[instrument(name = "source", skip_all, fields(source = name))]
+pub async fn spray_source_loop(
+    output: Sender<SourceMessage>,
+    name: Name,
+    options: SprayOptions
+) -> anyhow::Result<()>
+{
+    let request = json!({
+        "jsonrpc": "2.0",
+        "id": 0,
+        "method": "spraySubscribe",
+        "params": [relay_query(&options.query)]
+    }).to_string();
+
+    let mut backoff = Backoff::new(true);
+    while !output.is_closed() {
+        let mut update_received = false;
+        match spray_session(&output, name, &options, &request, &mut update_received).await {
+            Ok(_) => return Ok(()),
+            Err(err) => backoff.failure(name, err, update_received).await?
+        }
+    }
+    Ok(())
+}
+
+
+/// Adjusts the configured query, so that notifications carry enough data
+/// to reconstruct data messages and track slot commitment locally.
+fn relay_query(query: &SolanaQuery) -> SolanaQuery {
+    let mut query = query.clone();
+    query.fields = FieldSelection::all();
+    query.commitment = Commitment::Processed;
+    query.commitment_updates = true;
+    query.include_all_blocks = true;
+    for req in query.instructions.iter_mut() {
+        req.transaction = true;
+    }
+    for req in query.balances.iter_mut() {
+        req.transaction = true;
+    }
+    for req in query.token_balances.iter_mut() {
+        req.transaction = true;
+    }
+    for req in query.logs.iter_mut() {
+        req.transaction = true;
+    }
+    query
+}
+
+
+async fn spray_session(
+    output: &Sender<SourceMessage>,
+    name: Name,
+    options: &SprayOptions,
+    request: &str,
+    update_received: &mut bool
+) -> anyhow::Result<()>
+{
+    let (mut ws, _) = tokio_tungstenite::connect_async(options.url.as_str())
+        .await
+        .context("failed to connect")?;
+
+    ws.send(Message::text(request)).await?;
+
+    let idle = sleep(options.idle_timeout);
+    tokio::pin!(idle);
+
+    loop {
+        let msg = select! {
+            msg = ws.next() => {
+                match msg {
+                    Some(msg) => msg?,
+                    None => bail!("connection closed")
+                }
+            },
+            _ = &mut idle => {
+                bail!("haven't received updates for more than {} seconds", options.idle_timeout.as_secs())
+            }
+        };
+
+        idle.as_mut().reset(Instant::now() + options.idle_timeout);
+
+        let text = match msg {
+            Message::Text(text) => text,
+            Message::Close(frame) => bail!("connection closed by upstream: {:?}", frame),
+            _ => continue
+        };
+
+        let msg: ServerMessage = serde_json::from_str(&text).context("failed to parse upstream message")?;
+
+        if let Some(err) = msg.error {
+            bail!("spraySubscribe failed with error {}: {}", err.code, err.message)
+        }
+
+        let Some(params) = msg.params else {
+            debug!("subscribed to updates");
+            continue
+        };
+
+        let Some(notification) = params.result else {
+            bail!("upstream terminated the subscription")
+        };
+
+        if let Notification::Gap { skipped, from_slot, to_slot } = notification {
+            bail!("upstream skipped {} messages of slots {}..={}", skipped, from_slot, to_slot)
+        }
+
+        let update = match map_notification(notification) {
+            Ok(data) => SourceUpdate::Data(data),
+            Err(err) => {
+                error!(err =? err, "failed to map notification");
+                crate::metrics::register_mapping_error(name);
+                continue
+            }
+        };
+
+        *update_received = true;
+
+        if output.send(SourceMessage::new(name, update)).await.is_err() {
+            return Ok(())
+        }
+    }
+}
\ No newline at end of file
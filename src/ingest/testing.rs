@@ -0,0 +1,65 @@
+//! Builders of source updates for unit tests
+use super::mapping::{map_block, map_transaction};
+use super::source::{BlockUpdate, SourceUpdate, TransactionUpdate};
+use crate::data::DataMessage;
+use crate::geyser::api::{SlotStatus, SubscribeUpdateSlot};
+use crate::geyser::{mock_block, mock_transaction, MockBlock, MockTransaction};
+use std::sync::Arc;
+
+
+pub fn tx(slot: u64, index: u64) -> SourceUpdate {
+    let tx = mock_transaction(MockTransaction {
+        slot,
+        index,
+        accounts: Vec::new(),
+        is_vote: false,
+        failed: false
+    }, true);
+    SourceUpdate::Transaction(TransactionUpdate::from_subscription_update(tx).unwrap())
+}
+
+
+pub fn block(slot: u64, parent_slot: u64) -> SourceUpdate {
+    SourceUpdate::Block(BlockUpdate::new(mock_block(MockBlock {
+        slot,
+        parent_slot: Some(parent_slot),
+        blockhash: None,
+        parent_blockhash: None,
+        block_time: Some(0),
+        block_height: None,
+        executed_transaction_count: 0
+    })))
+}
+
+
+pub fn slot(slot: u64, parent: u64, status: SlotStatus) -> SourceUpdate {
+    SourceUpdate::Slot(SubscribeUpdateSlot {
+        slot,
+        parent: Some(parent),
+        status: status as i32,
+        dead_error: None
+    })
+}
+
+
+/// Maps a transaction or block update into a data message
+pub fn data(update: SourceUpdate) -> Arc<DataMessage> {
+    let msg = match update {
+        SourceUpdate::Transaction(tx) => DataMessage::Transaction(map_transaction(tx).unwrap()),
+        SourceUpdate::Block(block) => DataMessage::Block(map_block(block)),
+        upd => panic!("{:?} can't be mapped", upd)
+    };
+    Arc::new(msg)
+}
+
+
+/// Short description of a data message
+pub fn describe(msg: &DataMessage) -> String {
+    match msg {
+        DataMessage::Block(block) => format!("block {}", block.slot),
+        DataMessage::Transaction(tx) => format!("tx {}:{}", tx.slot, tx.transaction_index),
+        DataMessage::Account(acc) => format!("account {}", acc.slot),
+        DataMessage::Rollback(rollback) => format!("rollback {:?}", rollback.slots),
+        DataMessage::SlotStatus(status) => format!("{:?} {}", status.status, status.slot)
+    }
+}
\ No newline at end of file
@@ -71,12 +71,12 @@ impl JsonBuilder {
     pub fn binary(&mut self, val: impl AsRef<[u8]>) {
         let val = val.as_ref();
         let beg = self.out.len();
-        let end = beg + 4 + val.len();
+        let end = beg + 4 + val.len() * 2;
         self.out.resize(end, 0);
         self.out[beg] = b'"';
         self.out[beg + 1] = b'0';
         self.out[beg + 2] = b'x';
-        faster_hex::hex_encode(val, &mut self.out).expect("hex encoding is infallible");
+        faster_hex::hex_encode(val, &mut self.out[beg + 3..end - 1]).expect("hex encoding is infallible");
         self.out[end - 1] = b'"';
     }
 
@@ -180,6 +180,21 @@ impl<'a> RawJson<'a> {
 }
 
 
+/// Owned version of [RawJson]
+#[derive(Clone)]
+pub struct RawJsonString(pub String);
+
+
+impl Serialize for RawJsonString {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        RawJson::new(&self.0).serialize(serializer)
+    }
+}
+
+
 impl<'a> Serialize for RawJson<'a> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -9,10 +9,11 @@ mod query;
 mod server;
 
 
-use crate::cli::CLI;
-use crate::config::Config;
-use crate::geyser::create_geyser_client;
-use crate::ingest::{Broadcast, Ingest};
+use crate::cli::{Command, CLI};
+use crate::config::{Config, GeyserMode, GeyserRole, RpcCommitment, SourceConfig};
+use crate::data::Commitment;
+use crate::geyser::{create_geyser_client, run_mock_geyser};
+use crate::ingest::{Broadcast, BufferOptions, FallbackOptions, FilterPushdown, Ingest, ReplayOptions, RpcSourceOptions, SourceMode, SourceOptions, SourceRole, SprayOptions};
 use crate::server::RpcServer;
 use anyhow::{ensure, Context};
 use clap::Parser;
@@ -25,7 +26,19 @@ static GLOBAL: tikv_jemallocator::Jemalloc = tikv_jemallocator::Jemalloc;
 
 fn main() -> anyhow::Result<()> {
     let args = CLI::parse();
-    let cfg = Config::read(args.config).context("failed to read config file")?;
+    
+    if let Some(Command::MockGeyser { script, port }) = args.command {
+        init_tracing();
+        return tokio::runtime::Builder::new_multi_thread()
+            .enable_all()
+            .build()?
+            .block_on(
+                run_mock_geyser(script.as_ref(), port)
+            )
+    }
+    
+    let config = args.config.expect("config argument is required by CLI");
+    let cfg = Config::read(config).context("failed to read config file")?;
     
     ensure!(!cfg.sources.is_empty(), "no data source was specified in config file");
     
@@ -41,21 +54,104 @@ fn main() -> anyhow::Result<()> {
 
 
 async fn run(cfg: Config) -> anyhow::Result<()> {
-    let broadcast = Broadcast::new(20_000);
+    // with pushdown the buffer would silently miss data, that no active subscription requested
+    ensure!(
+        cfg.buffer.slots == 0 || !cfg.sources.values().any(|src| {
+            matches!(src, SourceConfig::Geyser(src) if src.filter_pushdown)
+        }),
+        "buffer can't be used together with filter_pushdown"
+    );
+    
+    let broadcast = Broadcast::new(20_000, BufferOptions {
+        max_slots: cfg.buffer.slots,
+        max_bytes: cfg.buffer.memory_mb * 1024 * 1024
+    });
+    let pushdown = FilterPushdown::new();
     
     let mut ingest = {
         let mut ingest = Ingest::new();
+        let primary_sources = cfg.sources.values().filter(|src| {
+            !matches!(src, SourceConfig::Geyser(src) if src.role == GeyserRole::Backup)
+        }).count();
+        ensure!(primary_sources > 0, "at least one source must be primary");
+        // updates of backup sources are dropped before the quorum, while the fallback is not active
+        if let Some(quorum) = cfg.quorum {
+            ensure!(
+                quorum > 0 && quorum <= primary_sources,
+                "quorum must be between 1 and the number of primary sources ({})",
+                primary_sources
+            );
+            ingest.set_quorum(quorum);
+        }
+        if let Some(fallback) = cfg.fallback {
+            ingest.set_fallback(FallbackOptions {
+                lag_slots: fallback.lag_slots,
+                timeout: fallback.timeout
+            });
+        }
         for (name, src) in cfg.sources {
             let name: Name = name.leak();
-            let client = create_geyser_client(src).await.with_context(|| {
-                format!("{} connection failed", name)
-            })?;
-            ingest.add_source(name, client);
+            match src {
+                SourceConfig::Geyser(src) => {
+                    ensure!(
+                        !src.lazy || src.role == GeyserRole::Backup,
+                        "{} is not a backup source and can't be lazy",
+                        name
+                    );
+                    let client = create_geyser_client(&src).await.with_context(|| {
+                        format!("{} connection failed", name)
+                    })?;
+                    ingest.add_source(name, client, SourceOptions {
+                        votes: src.votes,
+                        accounts: src.accounts,
+                        filter_pushdown: src.filter_pushdown,
+                        ping_interval: src.ping_interval,
+                        idle_timeout: src.idle_timeout,
+                        record: src.record,
+                        mode: match src.mode {
+                            GeyserMode::Transactions => SourceMode::Transactions,
+                            GeyserMode::Blocks => SourceMode::Blocks
+                        },
+                        role: match (src.role, src.lazy) {
+                            (GeyserRole::Primary, _) => SourceRole::Primary,
+                            (GeyserRole::Backup, false) => SourceRole::Backup,
+                            (GeyserRole::Backup, true) => SourceRole::LazyBackup
+                        }
+                    });
+                },
+                SourceConfig::Rpc(src) => {
+                    ingest.add_rpc_source(name, RpcSourceOptions {
+                        url: src.url,
+                        commitment: match src.commitment {
+                            RpcCommitment::Confirmed => Commitment::Confirmed,
+                            RpcCommitment::Finalized => Commitment::Finalized
+                        },
+                        poll_interval: src.poll_interval,
+                        votes: src.votes
+                    });
+                },
+                SourceConfig::File(src) => {
+                    ingest.add_replay_source(name, ReplayOptions {
+                        path: src.path,
+                        speed: src.speed,
+                        start_delay: src.start_delay,
+                        votes: src.votes,
+                        accounts: src.accounts
+                    });
+                },
+                SourceConfig::Spray(src) => {
+                    ingest.add_spray_source(name, SprayOptions {
+                        url: src.url,
+                        query: src.query,
+                        idle_timeout: src.idle_timeout
+                    });
+                }
+            }
         }
-        ingest.start(broadcast.clone())
+        ingest.start(broadcast.clone(), pushdown.clone())
     };
 
-    let server_handle = RpcServer::new(broadcast)
+    let server_handle = RpcServer::new(broadcast, pushdown)
         .set_port(cfg.port.unwrap_or(3000))
         .start()
         .await?;
@@ -3,10 +3,12 @@ use prometheus_client::encoding::EncodeLabelSet;
 use prometheus_client::metrics::counter::Counter;
 use prometheus_client::metrics::family::Family;
 use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::{Registry, Unit};
 use std::ops::Deref;
 use std::sync::atomic::AtomicU64;
 use std::sync::LazyLock;
+use std::time::Duration;
 
 
 #[derive(Copy, Clone, Hash, Debug, Default, Ord, PartialOrd, Eq, PartialEq, EncodeLabelSet)]
@@ -36,9 +38,33 @@ metric!(UNPARSED_TRANSACTION_ERRORS, Counter);
 metric!(DATA_SOURCE_ERRORS, Family<SourceLabel, Counter>);
 metric!(TRANSACTIONS_PUBLISHED, Family<SourceLabel, Counter>);
 metric!(BLOCKS_PUBLISHED, Family<SourceLabel, Counter>);
+metric!(ACCOUNTS_PUBLISHED, Family<SourceLabel, Counter>);
+metric!(LATE_UPDATES, Family<SourceLabel, Counter>);
+metric!(DROPPED_UPDATES, Family<SourceLabel, Counter>);
+metric!(QUORUM_DISAGREEMENTS, Family<SourceLabel, Counter>);
+metric!(PUSHDOWN_FALLBACKS, Family<SourceLabel, Counter>);
+metric!(FIRST_DELIVERIES, Family<SourceLabel, Counter>);
+metric!(ROLLED_BACK_SLOTS, Counter);
+metric!(UNRELEASED_MESSAGES, Counter);
 metric!(LAST_BLOCK, Gauge<u64, AtomicU64>);
 metric!(LAST_BLOCK_TIMESTAMP, Gauge);
 metric!(ACTIVE_SUBSCRIPTIONS, Gauge);
+metric!(FALLBACK_ACTIVE, Gauge);
+
+
+static PING_RTT: LazyLock<Family<SourceLabel, Histogram>> = LazyLock::new(|| {
+    Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.001, 2.0, 14)))
+});
+
+
+static SOURCE_LATENCY: LazyLock<Family<SourceLabel, Histogram>> = LazyLock::new(|| {
+    Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.001, 2.0, 14)))
+});
+
+
+static DELIVERY_LAG: LazyLock<Family<SourceLabel, Histogram>> = LazyLock::new(|| {
+    Family::new_with_constructor(|| Histogram::new(exponential_buckets(0.001, 2.0, 14)))
+});
 
 
 pub fn register_mapping_error(source: Name) {
@@ -56,11 +82,56 @@ pub fn register_data_source_error(source: Name) {
 }
 
 
+pub fn register_ping_rtt(source: Name, rtt: Duration) {
+    PING_RTT.get_or_create(&src!(source)).observe(rtt.as_secs_f64());
+}
+
+
+pub fn register_source_latency(source: Name, latency: Duration) {
+    SOURCE_LATENCY.get_or_create(&src!(source)).observe(latency.as_secs_f64());
+}
+
+
+pub fn register_first_delivery(source: Name) {
+    FIRST_DELIVERIES.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn register_delivery_lag(source: Name, lag: Duration) {
+    DELIVERY_LAG.get_or_create(&src!(source)).observe(lag.as_secs_f64());
+}
+
+
 pub fn register_tx_publication(source: Name) {
     TRANSACTIONS_PUBLISHED.get_or_create(&src!(source)).inc();
 }
 
 
+pub fn register_account_publication(source: Name) {
+    ACCOUNTS_PUBLISHED.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn register_late_update(source: Name) {
+    LATE_UPDATES.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn register_dropped_update(source: Name) {
+    DROPPED_UPDATES.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn register_quorum_disagreement(source: Name) {
+    QUORUM_DISAGREEMENTS.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn set_fallback_active(active: bool) {
+    FALLBACK_ACTIVE.set(active as i64);
+}
+
+
 pub fn register_block_publication(source: Name, slot: u64, timestamp: i64) {
     BLOCKS_PUBLISHED.get_or_create(&src!(source)).inc();
     LAST_BLOCK.set(slot);
@@ -68,6 +139,21 @@ pub fn register_block_publication(source: Name, slot: u64, timestamp: i64) {
 }
 
 
+pub fn register_pushdown_fallback(source: Name) {
+    PUSHDOWN_FALLBACKS.get_or_create(&src!(source)).inc();
+}
+
+
+pub fn register_rollback(slots: usize) {
+    ROLLED_BACK_SLOTS.inc_by(slots as u64);
+}
+
+
+pub fn register_unreleased_messages(messages: usize) {
+    UNRELEASED_MESSAGES.inc_by(messages as u64);
+}
+
+
 pub fn register_subscription_scope() -> impl Drop {
     ACTIVE_SUBSCRIPTIONS.inc();
     SubscriptionGuard
@@ -103,6 +189,33 @@ pub fn create_metrics_registry() -> Registry {
         DATA_SOURCE_ERRORS.deref().clone()
     );
 
+    reg.register_with_unit(
+        "spray_ping_rtt",
+        "Round-trip time of data source pings",
+        Unit::Seconds,
+        PING_RTT.deref().clone()
+    );
+
+    reg.register_with_unit(
+        "spray_source_latency",
+        "Time between creation of an update by the data source and its local receipt",
+        Unit::Seconds,
+        SOURCE_LATENCY.deref().clone()
+    );
+
+    reg.register(
+        "spray_first_deliveries",
+        "Number of accepted transactions and blocks, that the data source delivered first",
+        FIRST_DELIVERIES.deref().clone()
+    );
+
+    reg.register_with_unit(
+        "spray_delivery_lag",
+        "Time between the accepted delivery of a transaction or block and its delivery by the data source",
+        Unit::Seconds,
+        DELIVERY_LAG.deref().clone()
+    );
+
     reg.register(
         "spray_transactions_published",
         "Number of transactions pushed to subscriptions",
@@ -115,6 +228,48 @@ pub fn create_metrics_registry() -> Registry {
         BLOCKS_PUBLISHED.deref().clone()
     );
     
+    reg.register(
+        "spray_accounts_published",
+        "Number of account updates pushed to subscriptions",
+        ACCOUNTS_PUBLISHED.deref().clone()
+    );
+    
+    reg.register(
+        "spray_late_updates",
+        "Number of accepted updates, that arrived after updates of a later slot",
+        LATE_UPDATES.deref().clone()
+    );
+    
+    reg.register(
+        "spray_dropped_updates",
+        "Number of updates dropped, because their slot was already closed or dead",
+        DROPPED_UPDATES.deref().clone()
+    );
+    
+    reg.register(
+        "spray_quorum_disagreements",
+        "Number of updates, that conflict with the version delivered by other sources",
+        QUORUM_DISAGREEMENTS.deref().clone()
+    );
+    
+    reg.register(
+        "spray_pushdown_fallbacks",
+        "Number of source subscriptions, that requested all data, because pushed down filters were too large",
+        PUSHDOWN_FALLBACKS.deref().clone()
+    );
+    
+    reg.register(
+        "spray_rolled_back_slots",
+        "Number of published slots, that were abandoned by the cluster",
+        ROLLED_BACK_SLOTS.deref().clone()
+    );
+    
+    reg.register(
+        "spray_unreleased_messages",
+        "Number of processed data messages, that were dropped before their release at higher commitment levels",
+        UNRELEASED_MESSAGES.deref().clone()
+    );
+    
     reg.register(
         "spray_last_block",
         "Last published block",
@@ -134,5 +289,11 @@ pub fn create_metrics_registry() -> Registry {
         ACTIVE_SUBSCRIPTIONS.deref().clone()
     );
 
+    reg.register(
+        "spray_fallback_active",
+        "Whether updates of backup sources are used",
+        FALLBACK_ACTIVE.deref().clone()
+    );
+
     reg
 }
\ No newline at end of file
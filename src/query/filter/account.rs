@@ -0,0 +1,80 @@
+use super::item_filter::ItemFilter;
+use crate::data::AccountData;
+use crate::query::util::parse_hex;
+use crate::query::AccountRequest;
+use std::collections::HashSet;
+
+
+pub type PreparedAccountRequest = ItemFilter<AccountData, ()>;
+
+
+pub struct AccountFilter {
+    requests: Vec<PreparedAccountRequest>
+}
+
+
+impl AccountFilter {
+    pub fn new(requests: Vec<AccountRequest>) -> Self {
+        let requests = requests
+            .into_iter()
+            .filter_map(compile_request)
+            .collect();
+
+        Self {
+            requests
+        }
+    }
+
+    pub fn eval(&self, account: &AccountData) -> bool {
+        self.requests.iter().any(|req| req.eval(account))
+    }
+}
+
+
+fn compile_request(req: AccountRequest) -> Option<PreparedAccountRequest> {
+    let mut filter = PreparedAccountRequest::default();
+
+    macro_rules! in_list {
+        ($name:ident) => {
+            if let Some(list) = req.$name {
+                if list.is_empty() {
+                    return None
+                }
+                let set: HashSet<_> = list.into_iter().collect();
+                filter.add(move |acc| set.contains(&acc.$name));
+            }
+        };
+    }
+    in_list!(pubkey);
+    in_list!(owner);
+
+    if let Some(lamports) = req.lamports {
+        if let Some(val) = lamports.eq {
+            filter.add(move |acc| acc.lamports == val);
+        }
+        if let Some(val) = lamports.ne {
+            filter.add(move |acc| acc.lamports != val);
+        }
+        if let Some(val) = lamports.lt {
+            filter.add(move |acc| acc.lamports < val);
+        }
+        if let Some(val) = lamports.gt {
+            filter.add(move |acc| acc.lamports > val);
+        }
+    }
+
+    if let Some(size) = req.data_size {
+        filter.add(move |acc| acc.data.len() as u64 == size);
+    }
+
+    for memcmp in req.memcmp.unwrap_or_default() {
+        let bytes = parse_hex(&memcmp.bytes)?;
+        let offset = usize::try_from(memcmp.offset).ok()?;
+        let end = offset.checked_add(bytes.len())?;
+        filter.add(move |acc| {
+            acc.data.get(offset..end) == Some(&bytes)
+        });
+    }
+
+    Some(filter)
+}
\ No newline at end of file
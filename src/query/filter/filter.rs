@@ -1,9 +1,12 @@
+use super::account::AccountFilter;
 use super::balance::BalanceFilter;
 use super::instruction::InstructionFilter;
+use super::log::LogFilter;
+use super::reward::RewardFilter;
 use super::token_balance::TokenBalanceFilter;
 use super::transaction::TransactionFilter;
-use crate::data::TransactionData;
-use crate::query::filter::selected_items::SelectedItems;
+use crate::data::{AccountData, BlockData, TransactionData};
+use crate::query::filter::selected_items::{ItemSelection, SelectedItems};
 use crate::query::SolanaQuery;
 
 
@@ -12,22 +15,36 @@ pub struct Filter {
     instruction: InstructionFilter,
     balance: BalanceFilter,
     token_balance: TokenBalanceFilter,
+    log: LogFilter,
+    reward: RewardFilter,
+    account: AccountFilter,
+    include_votes: bool,
 }
 
 
 impl Filter {
     pub fn compile(query: SolanaQuery) -> Self {
+        // vote transactions are only delivered when explicitly requested
+        let include_votes = query.transactions.iter().any(|req| req.is_vote == Some(true));
         Self {
             transaction: TransactionFilter::new(query.transactions),
             instruction: InstructionFilter::new(query.instructions),
             balance: BalanceFilter::new(query.balances),
-            token_balance: TokenBalanceFilter::new(query.token_balances)
+            token_balance: TokenBalanceFilter::new(query.token_balances),
+            log: LogFilter::new(query.logs),
+            reward: RewardFilter::new(query.rewards),
+            account: AccountFilter::new(query.accounts),
+            include_votes
         }
     }
     
     pub fn eval(&self, tx: &TransactionData) -> SelectedItems {
         let mut sel = SelectedItems::new_for_transaction(tx);
         
+        if tx.transaction.is_vote && !self.include_votes {
+            return sel
+        }
+        
         if self.transaction.is_non_trivial() {
             self.transaction.eval(&mut sel, tx)
         }
@@ -40,10 +57,28 @@ impl Filter {
             self.token_balance.eval(&mut sel, tx)
         }
         
+        if self.log.is_non_trivial() {
+            self.log.eval(&mut sel, tx)
+        }
+        
         if self.instruction.is_non_trivial() {
             self.instruction.eval(&mut sel, tx)
         }
         
         sel
     }
+    
+    pub fn eval_rewards(&self, block: &BlockData) -> ItemSelection {
+        let mut sel = ItemSelection::new(block.rewards.len());
+        
+        if self.reward.is_non_trivial() {
+            self.reward.eval(&mut sel, block)
+        }
+        
+        sel
+    }
+    
+    pub fn eval_account(&self, account: &AccountData) -> bool {
+        self.account.eval(account)
+    }
 }
\ No newline at end of file
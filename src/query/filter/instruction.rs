@@ -55,6 +55,10 @@ impl InstructionFilter {
 
                 sel.instructions.add(i);
 
+                if rel.has_logs() && !sel.logs.includes_all() {
+                    Self::eval_logs(sel, tx, i);
+                }
+
                 if !sel.instructions.includes_all() {
                     if rel.has_inner_instructions() {
                         Self::eval_inner_instructions(sel, tx, i);
@@ -68,6 +72,18 @@ impl InstructionFilter {
         }
     }
 
+    fn eval_logs(
+        sel: &mut SelectedItems,
+        tx: &TransactionData,
+        instruction_index: usize
+    ) {
+        for (i, log) in tx.logs.iter().enumerate() {
+            if log.instruction_index == instruction_index {
+                sel.logs.add(i);
+            }
+        }
+    }
+
     fn eval_inner_instructions(
         sel: &mut SelectedItems,
         tx: &TransactionData,
@@ -0,0 +1,77 @@
+use super::item_filter::ItemFilter;
+use super::relation_mask::relation_mask;
+use super::selected_items::SelectedItems;
+use crate::data::{LogMessage, TransactionData};
+use crate::query::LogRequest;
+use std::collections::HashSet;
+
+
+relation_mask! {
+    LogRelations {
+        transaction,
+        instruction,
+    }
+}
+
+
+pub type PreparedLogRequest = ItemFilter<LogMessage, LogRelations>;
+
+
+pub struct LogFilter {
+    requests: Vec<PreparedLogRequest>
+}
+
+
+impl LogFilter {
+    pub fn new(requests: Vec<LogRequest>) -> Self {
+        let requests = requests
+            .into_iter()
+            .filter_map(compile_request)
+            .collect();
+
+        Self {
+            requests
+        }
+    }
+
+    pub fn is_non_trivial(&self) -> bool {
+        !self.requests.is_empty()
+    }
+
+    pub fn eval(&self, sel: &mut SelectedItems, tx: &TransactionData) {
+        for (i, log) in tx.logs.iter().enumerate() {
+            if let Some(rel) = ItemFilter::or(&self.requests, log) {
+                sel.logs.add(i);
+                sel.transaction |= rel.has_transaction();
+                if rel.has_instruction() {
+                    sel.instructions.add(log.instruction_index);
+                }
+            }
+        }
+    }
+}
+
+
+fn compile_request(req: LogRequest) -> Option<PreparedLogRequest> {
+    let mut filter = PreparedLogRequest::default();
+
+    if let Some(list) = req.program_id {
+        if list.is_empty() {
+            return None
+        }
+        let set: HashSet<_> = list.into_iter().collect();
+        filter.add(move |log| set.contains(&log.program_id));
+    }
+
+    if let Some(list) = req.kind {
+        if list.is_empty() {
+            return None
+        }
+        filter.add(move |log| list.contains(&log.kind));
+    }
+
+    filter.relations_mut().set_transaction(req.transaction);
+    filter.relations_mut().set_instruction(req.instruction);
+
+    Some(filter)
+}
\ No newline at end of file
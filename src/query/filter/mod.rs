@@ -1,7 +1,10 @@
+mod account;
 mod balance;
 mod instruction;
 mod item_filter;
+mod log;
 mod relation_mask;
+mod reward;
 mod selected_items;
 mod token_balance;
 mod transaction;
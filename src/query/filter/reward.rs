@@ -0,0 +1,61 @@
+use super::item_filter::ItemFilter;
+use super::selected_items::ItemSelection;
+use crate::data::{BlockData, Reward};
+use crate::query::RewardRequest;
+use std::collections::HashSet;
+
+
+pub type PreparedRewardRequest = ItemFilter<Reward, ()>;
+
+
+pub struct RewardFilter {
+    requests: Vec<PreparedRewardRequest>
+}
+
+
+impl RewardFilter {
+    pub fn new(requests: Vec<RewardRequest>) -> Self {
+        let requests = requests
+            .into_iter()
+            .filter_map(compile_request)
+            .collect();
+
+        Self {
+            requests
+        }
+    }
+
+    pub fn is_non_trivial(&self) -> bool {
+        !self.requests.is_empty()
+    }
+
+    pub fn eval(&self, sel: &mut ItemSelection, block: &BlockData) {
+        for (i, reward) in block.rewards.iter().enumerate() {
+            if self.requests.iter().any(|req| req.eval(reward)) {
+                sel.add(i);
+            }
+        }
+    }
+}
+
+
+fn compile_request(req: RewardRequest) -> Option<PreparedRewardRequest> {
+    let mut filter = PreparedRewardRequest::default();
+
+    if let Some(list) = req.pubkey {
+        if list.is_empty() {
+            return None
+        }
+        let set: HashSet<_> = list.into_iter().collect();
+        filter.add(move |r| set.contains(&r.pubkey));
+    }
+
+    if let Some(list) = req.reward_type {
+        if list.is_empty() {
+            return None
+        }
+        filter.add(move |r| r.reward_type.is_some_and(|t| list.contains(&t)));
+    }
+
+    Some(filter)
+}
\ No newline at end of file
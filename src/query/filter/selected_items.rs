@@ -7,16 +7,28 @@ pub struct SelectedItems {
     pub instructions: ItemSelection,
     pub balances: ItemSelection,
     pub token_balances: ItemSelection,
+    pub logs: ItemSelection,
 }
 
 
 impl SelectedItems {
+    /// Selects the transaction with its instructions, balances and token balances
+    pub fn full_transaction(tx: &TransactionData) -> Self {
+        let mut sel = Self::new_for_transaction(tx);
+        sel.transaction = true;
+        sel.instructions.add_all(true);
+        sel.balances.add_all(true);
+        sel.token_balances.add_all(true);
+        sel
+    }
+
     pub fn new_for_transaction(tx: &TransactionData) -> Self {
         Self {
             transaction: false,
             instructions: ItemSelection::new(tx.instructions.len()),
             balances: ItemSelection::new(tx.balances.len()),
             token_balances: ItemSelection::new(tx.token_balances.len()),
+            logs: ItemSelection::new(tx.logs.len()),
         }
     }
     
@@ -25,6 +37,7 @@ impl SelectedItems {
             && self.instructions.is_empty() 
             && self.token_balances.is_empty() 
             && self.balances.is_empty()
+            && self.logs.is_empty()
     }
 }
 
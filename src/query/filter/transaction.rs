@@ -49,6 +49,7 @@ impl TransactionFilter {
         sel.instructions.add_all(rel.has_instructions());
         sel.balances.add_all(rel.has_balances());
         sel.token_balances.add_all(rel.has_token_balances());
+        sel.logs.add_all(rel.has_logs());
     }
 }
 
@@ -76,6 +77,10 @@ fn compile_request(req: TransactionRequest) -> Option<PreparedTransactionRequest
         })
     }
 
+    if let Some(is_vote) = req.is_vote {
+        filter.add(move |tx| tx.transaction.is_vote == is_vote)
+    }
+
     filter.relations_mut().set_instructions(req.instructions);
     filter.relations_mut().set_logs(req.logs);
     filter.relations_mut().set_balances(req.balances);
@@ -1,9 +1,11 @@
 mod filter;
 mod model;
 mod render;
+mod upstream;
 mod util;
 
 
 pub use filter::*;
 pub use model::*;
-pub use render::*;
\ No newline at end of file
+pub use render::*;
+pub use upstream::*;
\ No newline at end of file
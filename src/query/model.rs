@@ -1,4 +1,6 @@
 use anyhow::ensure;
+use std::time::Duration;
+use crate::data::{Commitment, LogKind, RewardType};
 use crate::query::util::{field_selection, item_field_selection, request};
 use serde::{Deserialize, Serialize};
 
@@ -9,6 +11,9 @@ field_selection! {
     instruction: InstructionFieldSelection,
     balance: BalanceFieldSelection,
     token_balance: TokenBalanceFieldSelection,
+    log: LogFieldSelection,
+    reward: RewardFieldSelection,
+    account: AccountFieldSelection,
 }
 
 
@@ -20,6 +25,7 @@ item_field_selection! {
         parent_hash,
         height,
         timestamp,
+        complete,
     }
 
     TransactionFieldSelection {
@@ -38,6 +44,7 @@ item_field_selection! {
         loaded_addresses,
         fee_payer,
         has_dropped_log_messages,
+        is_vote,
     }
 
     InstructionFieldSelection {
@@ -77,6 +84,34 @@ item_field_selection! {
         pre_amount,
         post_amount,
     }
+
+    LogFieldSelection {
+        transaction_index,
+        log_index,
+        instruction_address,
+        program_id,
+        kind,
+        message,
+    }
+
+    RewardFieldSelection {
+        pubkey,
+        lamports,
+        post_balance,
+        reward_type,
+        commission,
+    }
+
+    AccountFieldSelection {
+        pubkey,
+        lamports,
+        owner,
+        executable,
+        rent_epoch,
+        data,
+        write_version,
+        txn_signature,
+    }
 }
 
 
@@ -88,6 +123,7 @@ request! {
     pub struct TransactionRequest {
         pub fee_payer: Option<Vec<Base58Bytes>>,
         pub mentions_account: Option<Vec<Base58Bytes>>,
+        pub is_vote: Option<bool>,
         pub instructions: bool,
         pub logs: bool,
         pub balances: bool,
@@ -146,14 +182,74 @@ request! {
         pub transaction_instructions: bool,
     }
 
+    pub struct LogRequest {
+        pub program_id: Option<Vec<Base58Bytes>>,
+        pub kind: Option<Vec<LogKind>>,
+        pub transaction: bool,
+        pub instruction: bool,
+    }
+
+    pub struct RewardRequest {
+        pub pubkey: Option<Vec<Base58Bytes>>,
+        pub reward_type: Option<Vec<RewardType>>,
+    }
+
+    pub struct AccountRequest {
+        pub pubkey: Option<Vec<Base58Bytes>>,
+        pub owner: Option<Vec<Base58Bytes>>,
+        pub lamports: Option<LamportsFilter>,
+        pub data_size: Option<u64>,
+        pub memcmp: Option<Vec<MemcmpFilter>>,
+    }
+
+    pub struct LamportsFilter {
+        pub eq: Option<u64>,
+        pub ne: Option<u64>,
+        pub lt: Option<u64>,
+        pub gt: Option<u64>,
+    }
+
+    pub struct MemcmpFilter {
+        pub offset: u64,
+        pub bytes: Bytes,
+    }
+
     pub struct SolanaQuery {
         pub fields: FieldSelection,
+        pub commitment: Commitment,
+        pub commitment_updates: bool,
         pub include_all_blocks: bool,
+        pub lag_policy: LagPolicy,
+        pub from_slot: Option<u64>,
+        pub from_block: Option<u64>,
+        pub to_block: Option<u64>,
         pub transactions: Vec<TransactionRequest>,
         pub instructions: Vec<InstructionRequest>,
         pub balances: Vec<BalanceRequest>,
         pub token_balances: Vec<TokenBalanceRequest>,
+        pub logs: Vec<LogRequest>,
+        pub rewards: Vec<RewardRequest>,
+        pub accounts: Vec<AccountRequest>,
     }
+
+    pub struct SignatureQuery {
+        pub signatures: Vec<String>,
+        pub timeout: Option<u64>,
+    }
+}
+
+
+/// What to do, when a subscription falls behind and misses data messages
+#[derive(Debug, Default, Copy, Clone, Eq, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LagPolicy {
+    /// Continue silently
+    Ignore,
+    /// Send a `gap` notification and continue
+    #[default]
+    Notify,
+    /// Send a `gap` notification and terminate the subscription
+    Disconnect
 }
 
 
@@ -162,7 +258,10 @@ impl SolanaQuery {
         let num_items = self.transactions.len() 
             + self.instructions.len() 
             + self.balances.len() 
-            + self.token_balances.len();
+            + self.token_balances.len()
+            + self.logs.len()
+            + self.rewards.len()
+            + self.accounts.len();
 
         ensure!(
             num_items <= 100,
@@ -172,4 +271,50 @@ impl SolanaQuery {
 
         Ok(())
     }
+
+    pub fn validate_subscription(&self) -> anyhow::Result<()> {
+        self.validate()?;
+        ensure!(
+            self.from_block.is_none() && self.to_block.is_none(),
+            "fromBlock and toBlock are only supported by sprayQuery, use fromSlot to resume subscriptions"
+        );
+        Ok(())
+    }
+
+    pub fn validate_one_shot(&self) -> anyhow::Result<()> {
+        self.validate()?;
+        ensure!(self.from_slot.is_none(), "fromSlot is not supported by sprayQuery, use fromBlock");
+        if let (Some(from_block), Some(to_block)) = (self.from_block, self.to_block) {
+            ensure!(from_block <= to_block, "fromBlock is greater than toBlock");
+        }
+        Ok(())
+    }
+}
+
+
+impl SignatureQuery {
+    pub fn validate(&self) -> anyhow::Result<()> {
+        ensure!(!self.signatures.is_empty(), "no signatures were given");
+        ensure!(
+            self.signatures.len() <= 100,
+            "query contains {} signatures, but only 100 is allowed",
+            self.signatures.len()
+        );
+        for sig in self.signatures.iter() {
+            let is_valid = bs58::decode(sig).into_vec().is_ok_and(|bytes| bytes.len() == 64);
+            ensure!(is_valid, "invalid signature: {}", sig);
+        }
+        if let Some(timeout) = self.timeout {
+            ensure!(
+                (1..=600).contains(&timeout),
+                "timeout must be between 1 and 600 seconds"
+            );
+        }
+        Ok(())
+    }
+
+    /// Time after which the subscription is terminated
+    pub fn timeout(&self) -> Duration {
+        Duration::from_secs(self.timeout.unwrap_or(60))
+    }
 }
\ No newline at end of file
@@ -1,6 +1,6 @@
-use super::filter::SelectedItems;
-use super::{BlockFieldSelection, FieldSelection};
-use crate::data::{BlockData, TransactionData};
+use super::filter::{ItemSelection, SelectedItems};
+use super::FieldSelection;
+use crate::data::{AccountData, BlockData, Commitment, SlotStatusData, TransactionData};
 use crate::json_builder::{safe_prop, JsonBuilder};
 
 
@@ -82,7 +82,10 @@ pub fn render_transaction_message(fields: &FieldSelection, tx: &TransactionData,
                 });
             }
             if fields.has_dropped_log_messages {
-                safe_prop!(json, "hasDroppedLogMessages", json.raw("true"));
+                safe_prop!(json, "hasDroppedLogMessages", json.boolean(tx.has_dropped_log_messages));
+            }
+            if fields.is_vote {
+                safe_prop!(json, "isVote", json.boolean(tx.is_vote));
             }
             json.end_object();
         });
@@ -163,13 +166,19 @@ pub fn render_transaction_message(fields: &FieldSelection, tx: &TransactionData,
                     });
                 }
                 if fields.compute_units_consumed {
-                    safe_prop!(json, "computeUnitsConsumed", json.null());
+                    safe_prop!(json, "computeUnitsConsumed", {
+                        if let Some(val) = ins.compute_units_consumed {
+                            json.number_str(val)
+                        } else {
+                            json.null()
+                        }
+                    });
                 }
                 if fields.is_committed {
                     safe_prop!(json, "isCommitted", json.boolean(ins.is_committed));
                 }
                 if fields.has_dropped_log_messages {
-                    safe_prop!(json, "hasDroppedLogMessages", json.raw("true"));
+                    safe_prop!(json, "hasDroppedLogMessages", json.boolean(ins.has_dropped_log_messages));
                 }
                 json.end_object();
                 json.comma();
@@ -280,40 +289,75 @@ pub fn render_transaction_message(fields: &FieldSelection, tx: &TransactionData,
         });
     }
 
+    if !sel.logs.is_empty() {
+        safe_prop!(json, "logs", {
+            json.begin_array();
+            sel.logs.for_each_selected(|i| {
+                let log = &tx.logs[i];
+                let fields = &fields.log;
+                json.begin_object();
+                if fields.transaction_index {
+                     safe_prop!(json, "transactionIndex", json.number(tx.transaction_index));
+                }
+                if fields.log_index {
+                    safe_prop!(json, "logIndex", json.number(log.log_index));
+                }
+                if fields.instruction_address {
+                    safe_prop!(json, "instructionAddress", json.value(&log.instruction_address));
+                }
+                if fields.program_id {
+                    safe_prop!(json, "programId", json.safe_str(&log.program_id));
+                }
+                if fields.kind {
+                    safe_prop!(json, "kind", json.value(&log.kind));
+                }
+                if fields.message {
+                    safe_prop!(json, "message", json.str(&log.message));
+                }
+                json.end_object();
+                json.comma();
+            });
+            json.end_array();
+        });
+    }
+
     json.end_object();
     json.into_string()
 }
 
 
-pub fn render_block_message(fields: &BlockFieldSelection, block: &BlockData) -> String {
+pub fn render_block_message(fields: &FieldSelection, block: &BlockData, rewards: &ItemSelection) -> String {
     let mut json = JsonBuilder::new();
     json.begin_object();
     
     safe_prop!(json, "type", json.safe_str("block"));
     safe_prop!(json, "slot", json.number(block.slot));
     
-    if fields.number 
-        || fields.hash 
-        || fields.parent_number 
-        || fields.parent_hash 
-        || fields.height 
-        || fields.timestamp 
+    let header = &fields.block;
+    
+    if header.number 
+        || header.hash 
+        || header.parent_number 
+        || header.parent_hash 
+        || header.height 
+        || header.timestamp 
+        || header.complete
     {
         safe_prop!(json, "header", {
             json.begin_object();
-            if fields.number {
+            if header.number {
                 safe_prop!(json, "number", json.number(block.slot));
             }
-            if fields.hash {
+            if header.hash {
                 safe_prop!(json, "hash", json.safe_str(&block.hash));
             }
-            if fields.parent_number {
+            if header.parent_number {
                 safe_prop!(json, "parentNumber", json.number(block.parent_slot));
             }
-            if fields.parent_hash {
+            if header.parent_hash {
                 safe_prop!(json, "parentHash", json.safe_str(&block.parent_hash));
             }
-            if fields.height {
+            if header.height {
                 safe_prop!(json, "height", {
                     if let Some(height) = block.height {
                         json.number(height)
@@ -322,13 +366,163 @@ pub fn render_block_message(fields: &BlockFieldSelection, block: &BlockData) ->
                     }
                 });
             }
-            if fields.timestamp {
+            if header.timestamp {
                 safe_prop!(json, "timestamp", json.number(block.timestamp));
             }
+            if header.complete {
+                safe_prop!(json, "complete", {
+                    if let Some(complete) = block.complete {
+                        json.boolean(complete)
+                    } else {
+                        json.null()
+                    }
+                });
+            }
             json.end_object();
         });
     }
 
+    if !rewards.is_empty() {
+        safe_prop!(json, "rewards", {
+            json.begin_array();
+            rewards.for_each_selected(|i| {
+                let r = &block.rewards[i];
+                let fields = &fields.reward;
+                json.begin_object();
+                if fields.pubkey {
+                    safe_prop!(json, "pubkey", json.safe_str(&r.pubkey));
+                }
+                if fields.lamports {
+                    safe_prop!(json, "lamports", json.number_str(r.lamports));
+                }
+                if fields.post_balance {
+                    safe_prop!(json, "postBalance", json.number_str(r.post_balance));
+                }
+                if fields.reward_type {
+                    safe_prop!(json, "rewardType", {
+                        if let Some(t) = r.reward_type.as_ref() {
+                            json.value(t)
+                        } else {
+                            json.null()
+                        }
+                    });
+                }
+                if fields.commission {
+                    safe_prop!(json, "commission", {
+                        if let Some(val) = r.commission {
+                            json.number(val)
+                        } else {
+                            json.null()
+                        }
+                    });
+                }
+                json.end_object();
+                json.comma();
+            });
+            json.end_array();
+        });
+    }
+
+    json.end_object();
+    json.into_string()
+}
+
+
+pub fn render_account_message(fields: &FieldSelection, acc: &AccountData) -> String {
+    let mut json = JsonBuilder::new();
+    json.begin_object();
+
+    safe_prop!(json, "type", json.safe_str("account"));
+    safe_prop!(json, "slot", json.number(acc.slot));
+
+    safe_prop!(json, "account", {
+        let fields = &fields.account;
+        json.begin_object();
+        if fields.pubkey {
+            safe_prop!(json, "pubkey", json.safe_str(&acc.pubkey));
+        }
+        if fields.lamports {
+            safe_prop!(json, "lamports", json.number_str(acc.lamports));
+        }
+        if fields.owner {
+            safe_prop!(json, "owner", json.safe_str(&acc.owner));
+        }
+        if fields.executable {
+            safe_prop!(json, "executable", json.boolean(acc.executable));
+        }
+        if fields.rent_epoch {
+            safe_prop!(json, "rentEpoch", json.number_str(acc.rent_epoch));
+        }
+        if fields.data {
+            safe_prop!(json, "data", json.binary(&acc.data));
+        }
+        if fields.write_version {
+            safe_prop!(json, "writeVersion", json.number_str(acc.write_version));
+        }
+        if fields.txn_signature {
+            safe_prop!(json, "txnSignature", {
+                if let Some(sig) = acc.txn_signature.as_ref() {
+                    json.safe_str(sig)
+                } else {
+                    json.null()
+                }
+            });
+        }
+        json.end_object();
+    });
+
+    json.end_object();
+    json.into_string()
+}
+
+
+pub fn render_rollback_message(slots: &[u64]) -> String {
+    let mut json = JsonBuilder::new();
+    json.begin_object();
+    safe_prop!(json, "type", json.safe_str("rollback"));
+    safe_prop!(json, "slots", json.number_list(slots.iter().copied()));
+    json.end_object();
+    json.into_string()
+}
+
+
+pub fn render_gap_message(skipped: u64, from_slot: u64, to_slot: u64) -> String {
+    let mut json = JsonBuilder::new();
+    json.begin_object();
+    safe_prop!(json, "type", json.safe_str("gap"));
+    safe_prop!(json, "skipped", json.number(skipped));
+    safe_prop!(json, "fromSlot", json.number(from_slot));
+    safe_prop!(json, "toSlot", json.number(to_slot));
+    json.end_object();
+    json.into_string()
+}
+
+
+pub fn render_slot_status_message(status: &SlotStatusData) -> String {
+    let mut json = JsonBuilder::new();
+    json.begin_object();
+    safe_prop!(json, "type", json.safe_str("commitment"));
+    safe_prop!(json, "slot", json.number(status.slot));
+    safe_prop!(json, "status", json.value(&status.status));
+    json.end_object();
+    json.into_string()
+}
+
+
+pub fn render_signature_message(signature: &str, slot: u64, status: Commitment, err: Option<&str>) -> String {
+    let mut json = JsonBuilder::new();
+    json.begin_object();
+    safe_prop!(json, "type", json.safe_str("signature"));
+    safe_prop!(json, "signature", json.safe_str(signature));
+    safe_prop!(json, "slot", json.number(slot));
+    safe_prop!(json, "status", json.value(&status));
+    safe_prop!(json, "err", {
+        if let Some(err) = err {
+            json.raw(err)
+        } else {
+            json.null()
+        }
+    });
     json.end_object();
     json.into_string()
 }
\ No newline at end of file
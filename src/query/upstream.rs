@@ -0,0 +1,97 @@
+use super::{Base58Bytes, SolanaQuery};
+use std::collections::BTreeSet;
+
+
+/// Describes a superset of data source updates, that is required to serve a query.
+///
+/// Used to push filters upstream to the data source.
+#[derive(Debug, Default, Clone, Eq, PartialEq)]
+pub struct UpstreamFilter {
+    /// Whether all transactions are required
+    pub all_transactions: bool,
+    /// Transactions mentioning any of the given accounts are required
+    pub transaction_accounts: BTreeSet<Base58Bytes>,
+    /// Transactions with the given signatures are required
+    pub signatures: BTreeSet<Base58Bytes>,
+    /// Whether all account updates are required
+    pub all_accounts: bool,
+    /// Updates of the given accounts are required
+    pub account_pubkeys: BTreeSet<Base58Bytes>,
+    /// Updates of accounts owned by the given programs are required
+    pub account_owners: BTreeSet<Base58Bytes>,
+}
+
+
+impl UpstreamFilter {
+    pub fn from_query(query: &SolanaQuery) -> Self {
+        let mut filter = Self::default();
+
+        for req in query.transactions.iter() {
+            let accounts = req.fee_payer.as_ref().or(req.mentions_account.as_ref());
+            filter.include_transactions(accounts);
+        }
+
+        for req in query.instructions.iter() {
+            let accounts = [
+                &req.program_id,
+                &req.mentions_account,
+                &req.a0,
+                &req.a1,
+                &req.a2,
+                &req.a3,
+                &req.a4,
+                &req.a5,
+                &req.a6,
+                &req.a7,
+                &req.a8,
+                &req.a9,
+                &req.a10,
+                &req.a11,
+                &req.a12,
+                &req.a13,
+                &req.a14,
+                &req.a15,
+            ].into_iter().find_map(|list| list.as_ref());
+            filter.include_transactions(accounts);
+        }
+
+        for req in query.balances.iter() {
+            filter.include_transactions(req.account.as_ref());
+        }
+
+        for req in query.token_balances.iter() {
+            // mints, owners and programs of token accounts are not necessarily mentioned by transaction
+            filter.include_transactions(req.account.as_ref());
+        }
+
+        for req in query.logs.iter() {
+            filter.include_transactions(req.program_id.as_ref());
+        }
+
+        for req in query.accounts.iter() {
+            match (req.pubkey.as_ref(), req.owner.as_ref()) {
+                (Some(pubkeys), _) => filter.account_pubkeys.extend(pubkeys.iter().cloned()),
+                (None, Some(owners)) => filter.account_owners.extend(owners.iter().cloned()),
+                (None, None) => filter.all_accounts = true
+            }
+        }
+
+        filter
+    }
+
+    fn include_transactions(&mut self, accounts: Option<&Vec<Base58Bytes>>) {
+        match accounts {
+            Some(list) => self.transaction_accounts.extend(list.iter().cloned()),
+            None => self.all_transactions = true
+        }
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        self.all_transactions |= other.all_transactions;
+        self.transaction_accounts.extend(other.transaction_accounts.iter().cloned());
+        self.signatures.extend(other.signatures.iter().cloned());
+        self.all_accounts |= other.all_accounts;
+        self.account_pubkeys.extend(other.account_pubkeys.iter().cloned());
+        self.account_owners.extend(other.account_owners.iter().cloned());
+    }
+}
\ No newline at end of file
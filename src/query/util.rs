@@ -17,6 +17,14 @@ macro_rules! item_field_selection {
                 pub $field: bool
             ),*
         }
+
+        impl $type_name {
+            pub fn all() -> Self {
+                Self {
+                    $( $field: true ),*
+                }
+            }
+        }
         )*
     };
 }
@@ -35,6 +43,15 @@ macro_rules! field_selection {
                 pub $item_name: $field_selection,
             )*
         }
+
+        impl FieldSelection {
+            /// Selects all fields of all items
+            pub fn all() -> Self {
+                Self {
+                    $( $item_name: <$field_selection>::all(), )*
+                }
+            }
+        }
     };
 }
 pub(crate) use field_selection;
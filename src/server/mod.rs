@@ -4,30 +4,36 @@ mod metrics;
 
 use self::metrics::MetricsLayer;
 use self::rpc::build_rpc_module;
-use crate::ingest::Broadcast;
+use crate::ingest::{Broadcast, FilterPushdown};
 use crate::metrics::create_metrics_registry;
 use jsonrpsee::server::{Server, ServerConfig, ServerHandle};
 use std::sync::Arc;
 use tracing::info;
 
 
+/// Maximum size of a response body
+const MAX_RESPONSE_SIZE: u32 = 4 * 1024 * 1024;
+
+
 pub struct RpcServer {
     broadcast: Broadcast,
+    pushdown: FilterPushdown,
     config: ServerConfig,
     port: u16
 }
 
 
 impl RpcServer {
-    pub fn new(broadcast: Broadcast) -> Self {
+    pub fn new(broadcast: Broadcast, pushdown: FilterPushdown) -> Self {
         let config = ServerConfig::builder()
             .set_message_buffer_capacity(5)
-            .max_response_body_size(4 * 1024 * 1024)
+            .max_response_body_size(MAX_RESPONSE_SIZE)
             .max_request_body_size(257 * 1024)
             .build();
         
         Self {
             broadcast,
+            pushdown,
             config,
             port: 3000
         }
@@ -50,7 +56,7 @@ impl RpcServer {
             .build(("0.0.0.0", self.port))
             .await?;
 
-        let rpc = build_rpc_module(self.broadcast);
+        let rpc = build_rpc_module(self.broadcast, self.pushdown);
         
         let addr = server.local_addr()?;
         let handle = server.start(rpc);
@@ -1,21 +1,34 @@
-use crate::data::{DataMessage, JsonString};
-use crate::ingest::Broadcast;
-use crate::json_builder::RawJson;
-use crate::query::{render_block_message, render_transaction_message, FieldSelection, Filter, SolanaQuery};
+use crate::data::{Commitment, DataMessage, JsonString};
+use super::MAX_RESPONSE_SIZE;
+use crate::ingest::{Broadcast, FilterPushdown};
+use anyhow::bail;
+use crate::json_builder::{RawJson, RawJsonString};
+use crate::query::{render_account_message, render_block_message, render_gap_message, render_rollback_message, render_signature_message, render_slot_status_message, render_transaction_message, FieldSelection, Filter, LagPolicy, SelectedItems, SignatureQuery, SolanaQuery, UpstreamFilter};
 use jsonrpsee::types::{ErrorCode, ErrorObject};
-use jsonrpsee::{RpcModule, SubscriptionMessage};
+use jsonrpsee::{RpcModule, SubscriptionMessage, SubscriptionSink};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
 use tokio::select;
 use tokio::sync::broadcast::error::RecvError;
 use tracing::{debug, debug_span, Instrument};
 
 
-pub fn build_rpc_module(broadcast: Broadcast) -> RpcModule<Broadcast> {
-    let mut rpc = RpcModule::new(broadcast);
+pub struct RpcContext {
+    broadcast: Broadcast,
+    pushdown: FilterPushdown
+}
+
+
+pub fn build_rpc_module(broadcast: Broadcast, pushdown: FilterPushdown) -> RpcModule<RpcContext> {
+    let mut rpc = RpcModule::new(RpcContext {
+        broadcast,
+        pushdown
+    });
     rpc.register_subscription_raw(
         "spraySubscribe",
         "sprayNotification",
         "sprayUnsubscribe",
-        |params, pending, broadcast, _| {
+        |params, pending, ctx, _| {
             let span = debug_span!("subscription", connection_id = pending.connection_id().0);
             let span_guard = span.enter();
 
@@ -31,7 +44,7 @@ pub fn build_rpc_module(broadcast: Broadcast) -> RpcModule<Broadcast> {
                 }
             };
             
-            if let Err(err) = SolanaQuery::validate(&query) {
+            if let Err(err) = query.validate_subscription() {
                 let msg = format!("invalid query: {}", err);
                 debug!(msg);
                 
@@ -49,6 +62,28 @@ pub fn build_rpc_module(broadcast: Broadcast) -> RpcModule<Broadcast> {
                 query =% serde_json::to_string(&query).unwrap(),
             );
             
+            let commitment = query.commitment;
+            let lag_policy = query.lag_policy;
+            
+            let (buffered, mut rx) = match query.from_slot {
+                Some(from_slot) => match ctx.broadcast.subscribe_from(commitment, from_slot) {
+                    Ok(subscription) => subscription,
+                    Err(err) => {
+                        let msg = err.to_string();
+                        debug!(msg);
+                        let err = ErrorObject::owned::<()>(
+                            ErrorCode::InvalidParams.code(),
+                            msg,
+                            None
+                        );
+                        tokio::spawn(pending.reject(err));
+                        return
+                    }
+                },
+                None => (Vec::new(), ctx.broadcast.subscribe(commitment))
+            };
+            
+            let upstream_filter = ctx.pushdown.register(UpstreamFilter::from_query(&query));
             let mut state = SubscriptionState::new(query);
 
             drop(span_guard);
@@ -64,8 +99,24 @@ pub fn build_rpc_module(broadcast: Broadcast) -> RpcModule<Broadcast> {
                 
                 debug!("accepted");
                 let _scope = crate::metrics::register_subscription_scope();
+                let _upstream_filter = upstream_filter;
 
-                let mut rx = broadcast.subscribe();
+                // slot of the last received message, used to report gaps
+                let mut last_slot = None;
+                // number of skipped messages, reported along with the slot of the next message
+                let mut skipped_messages = None;
+                
+                for msg in buffered {
+                    last_slot = msg.slot().or(last_slot);
+                    let Some(msg) = state.emit(&msg) else {
+                        continue
+                    };
+                    if !send_notification(&sink, &msg).await {
+                        debug!("closed");
+                        return
+                    }
+                }
+                
                 loop {
                     select! {
                         biased;
@@ -76,76 +127,340 @@ pub fn build_rpc_module(broadcast: Broadcast) -> RpcModule<Broadcast> {
                         event = rx.recv() => {
                             match event {
                                 Ok(msg) => {
-                                    if let Some(msg) = state.emit(&msg) {
-                                        let msg = SubscriptionMessage::new(
-                                            sink.method_name(),
-                                            sink.subscription_id(),
-                                            &RawJson::new(&msg)
-                                        ).expect(
-                                            "serialization is infallible"
-                                        );
-                                        if sink.send(msg).await.is_err() {
+                                    let slot = msg.slot();
+                                    if let Some(skipped) = skipped_messages.take() {
+                                        let to_slot = slot.or(last_slot).unwrap_or_default();
+                                        let from_slot = last_slot.unwrap_or(to_slot);
+                                        let gap = render_gap_message(skipped, from_slot, to_slot);
+                                        if !send_notification(&sink, &gap).await {
                                             debug!("closed");
                                             return
                                         }
+                                        if lag_policy == LagPolicy::Disconnect {
+                                            debug!("terminating after a gap");
+                                            send_notification(&sink, "null").await;
+                                            return
+                                        }
+                                    }
+                                    last_slot = slot.or(last_slot);
+                                    let Some(msg) = state.emit(&msg) else {
+                                        continue
+                                    };
+                                    if !send_notification(&sink, &msg).await {
+                                        debug!("closed");
+                                        return
                                     }
                                 },
                                 Err(RecvError::Lagged(skipped)) => {
                                     debug!(skipped = skipped, "lagging behind");
+                                    if lag_policy != LagPolicy::Ignore {
+                                        *skipped_messages.get_or_insert(0) += skipped;
+                                    }
                                     continue
                                 },
                                 Err(RecvError::Closed) => {
                                     debug!("terminating");
-                                    let eof = SubscriptionMessage::new(
-                                        sink.method_name(),
-                                        sink.subscription_id(),
-                                        &serde_json::value::Value::Null
-                                    ).expect(
-                                        "serialization is infallible"
-                                    );
-                                    let _ = sink.send(eof).await;
+                                    send_notification(&sink, "null").await;
+                                    return
+                                }
+                            }
+                        }
+                    }
+                }
+            }.instrument(span));
+        }
+    ).unwrap();
+    rpc.register_subscription_raw(
+        "spraySignatureSubscribe",
+        "spraySignatureNotification",
+        "spraySignatureUnsubscribe",
+        |params, pending, ctx, _| {
+            let span = debug_span!("signature_subscription", connection_id = pending.connection_id().0);
+            let span_guard = span.enter();
+
+            let query = match params.one::<SignatureQuery>() {
+                Ok(query) => query,
+                Err(err) => {
+                    debug!(
+                        "invalid query - {}",
+                        err.data().map_or("unknown syntax error", |json| json.get())
+                    );
+                    tokio::spawn(pending.reject(err));
+                    return
+                }
+            };
+
+            if let Err(err) = query.validate() {
+                let msg = format!("invalid query: {}", err);
+                debug!(msg);
+
+                let err = ErrorObject::owned::<()>(
+                    ErrorCode::InvalidParams.code(),
+                    msg,
+                    None
+                );
+
+                tokio::spawn(pending.reject(err));
+                return
+            }
+
+            debug!(signatures =? query.signatures);
+
+            let timeout = query.timeout();
+            let (buffered, mut rx) = ctx.broadcast.subscribe_signatures(&query.signatures);
+            let upstream_filter = ctx.pushdown.register(UpstreamFilter {
+                signatures: query.signatures.iter().cloned().collect(),
+                ..UpstreamFilter::default()
+            });
+            let broadcast = ctx.broadcast.clone();
+            let mut state = SignatureState::new(query.signatures);
+
+            drop(span_guard);
+
+            tokio::spawn(async move {
+                let sink = match pending.accept().await {
+                    Ok(sink) => sink,
+                    Err(_) => {
+                        debug!("closed before acceptance");
+                        return
+                    }
+                };
+
+                debug!("accepted");
+                let _scope = crate::metrics::register_subscription_scope();
+                let _upstream_filter = upstream_filter;
+
+                let deadline = tokio::time::sleep(timeout);
+                tokio::pin!(deadline);
+
+                for msg in buffered {
+                    for msg in state.emit(&msg) {
+                        if !send_notification(&sink, &msg).await {
+                            debug!("closed");
+                            return
+                        }
+                    }
+                }
+
+                while !state.is_complete() {
+                    select! {
+                        biased;
+                        _ = sink.closed() => {
+                            debug!("closed");
+                            return
+                        },
+                        _ = &mut deadline => {
+                            debug!("timed out");
+                            break
+                        },
+                        event = rx.recv() => {
+                            let messages = match event {
+                                Ok(msg) => state.emit(&msg),
+                                Err(RecvError::Lagged(skipped)) => {
+                                    debug!(skipped = skipped, "lagging behind");
+                                    // commitment updates can't be recovered, but transactions are indexed
+                                    state.recover(&broadcast)
+                                },
+                                Err(RecvError::Closed) => break
+                            };
+                            for msg in messages {
+                                if !send_notification(&sink, &msg).await {
+                                    debug!("closed");
                                     return
                                 }
                             }
                         }
                     }
                 }
+
+                debug!("terminating");
+                send_notification(&sink, "null").await;
             }.instrument(span));
         }
     ).unwrap();
+    rpc.register_blocking_method("sprayQuery", |params, ctx, _| {
+        let query = params.one::<SolanaQuery>()?;
+        
+        if let Err(err) = query.validate_one_shot() {
+            return Err(ErrorObject::owned::<()>(
+                ErrorCode::InvalidParams.code(),
+                format!("invalid query: {}", err),
+                None
+            ))
+        }
+        
+        debug!(
+            query =% serde_json::to_string(&query).unwrap(),
+            "one-shot query"
+        );
+        
+        // leave room for the rest of the response
+        let max_size = MAX_RESPONSE_SIZE as usize - 1024;
+        
+        run_one_shot_query(&ctx.broadcast, query, max_size).map(RawJsonString).map_err(|err| {
+            ErrorObject::owned::<()>(
+                ErrorCode::InvalidParams.code(),
+                err.to_string(),
+                None
+            )
+        })
+    }).unwrap();
+    rpc.register_method("sprayGetTransaction", |params, ctx, _| {
+        let mut params = params.sequence();
+        let signature: String = params.next()?;
+        let fields: FieldSelection = params.optional_next()?.unwrap_or_else(FieldSelection::all);
+        
+        let msg = ctx.broadcast.get_transaction(&signature).map_err(|err| {
+            ErrorObject::owned::<()>(
+                ErrorCode::InvalidRequest.code(),
+                err.to_string(),
+                None
+            )
+        })?;
+        
+        let rendered = msg.map(|msg| {
+            let DataMessage::Transaction(tx) = msg.as_ref() else {
+                unreachable!("signature index holds only transactions")
+            };
+            let sel = SelectedItems::full_transaction(tx);
+            RawJsonString(render_transaction_message(&fields, tx, &sel))
+        });
+        Ok::<_, ErrorObject>(rendered)
+    }).unwrap();
     rpc
 }
 
 
+/// Evaluates the query against buffered messages and returns matching data messages
+/// along with the last slot, that was fully covered.
+///
+/// Stops at a slot boundary, when the output reaches `max_size`.
+/// Data of rolled back slots is excluded, commitment updates are not reported.
+fn run_one_shot_query(broadcast: &Broadcast, query: SolanaQuery, max_size: usize) -> anyhow::Result<String> {
+    let commitment = query.commitment;
+    let mut from_block = query.from_block;
+    let to_block = query.to_block.unwrap_or(u64::MAX);
+    
+    let mut state = SubscriptionState::new(query);
+    state.from_slot = from_block.unwrap_or_default();
+    state.block_heartbeat = false;
+    
+    let mut out = String::from("{\"data\":[");
+    let mut last_block = None;
+    // slot, whose messages are being rendered, and the output length before them
+    let mut current: Option<(u64, usize)> = None;
+    'chunks: loop {
+        // the buffer is locked only for a chunk at a time
+        let chunk = match broadcast.read_data(commitment, from_block, to_block, 1000) {
+            Ok(chunk) => chunk,
+            Err(err) if last_block.is_none() => return Err(err),
+            // the rest was evicted in the meantime
+            Err(_) => break
+        };
+        for (slot, msg) in chunk.messages.iter() {
+            match current {
+                Some((current_slot, _)) if current_slot == *slot => {},
+                Some(_) => {
+                    last_block = Some(slot - 1);
+                    current = Some((*slot, out.len()));
+                },
+                None => {
+                    current = Some((*slot, out.len()));
+                }
+            }
+            let Some(json) = state.emit(msg) else {
+                continue
+            };
+            if out.len() + json.len() + 1 > max_size {
+                if last_block.is_none() {
+                    bail!("data of slot {} exceeds the response size limit", slot)
+                }
+                // drop the incomplete slot
+                out.truncate(current.map_or(out.len(), |(_, len)| len));
+                break 'chunks
+            }
+            if !out.ends_with('[') {
+                out.push(',');
+            }
+            out.push_str(&json);
+        }
+        last_block = Some(chunk.last_slot);
+        if chunk.complete {
+            break
+        }
+        from_block = Some(chunk.last_slot + 1);
+    }
+    out.push_str("],\"lastBlock\":");
+    out.push_str(&last_block.unwrap_or_default().to_string());
+    out.push('}');
+    Ok(out)
+}
+
+
+/// Sends a raw JSON notification, returns `false` if the subscription is closed
+async fn send_notification(sink: &SubscriptionSink, json: &str) -> bool {
+    let msg = SubscriptionMessage::new(
+        sink.method_name(),
+        sink.subscription_id(),
+        &RawJson::new(json)
+    ).expect(
+        "serialization is infallible"
+    );
+    sink.send(msg).await.is_ok()
+}
+
+
 struct SubscriptionState {
     fields: FieldSelection,
     filter: Filter,
     include_all_blocks: bool,
+    commitment_updates: bool,
+    /// Data of earlier slots is not emitted
+    from_slot: u64,
+    /// Whether to emit a block every 5 slots, even if it has no matching data
+    block_heartbeat: bool,
     last_emitted_block: u64,
-    last_non_empty_block: u64
+    last_non_empty_block: u64,
+    /// Slots with emitted data, that might still be rolled back or upgraded
+    emitted_slots: HashSet<u64>
 }
 
 
+/// Number of recent slots with emitted data, that are tracked by a subscription
+const MAX_EMITTED_SLOTS: usize = 1000;
+
+
 impl SubscriptionState {
     fn new(query: SolanaQuery) -> Self {
         Self {
             fields: query.fields.clone(),
             include_all_blocks: query.include_all_blocks,
+            commitment_updates: query.commitment_updates,
+            from_slot: query.from_slot.unwrap_or_default(),
             filter: Filter::compile(query),
+            block_heartbeat: true,
             last_emitted_block: 0,
-            last_non_empty_block: 0
+            last_non_empty_block: 0,
+            emitted_slots: HashSet::new()
         }
     }
     
     fn emit(&mut self, msg: &DataMessage) -> Option<JsonString> {
+        let is_data = matches!(msg, DataMessage::Block(_) | DataMessage::Transaction(_) | DataMessage::Account(_));
+        if is_data && msg.slot().is_some_and(|slot| slot < self.from_slot) {
+            return None
+        }
         match msg {
             DataMessage::Block(block) => {
+                let rewards = self.filter.eval_rewards(block);
                 if self.include_all_blocks 
-                    || self.last_emitted_block + 5 <= block.slot 
+                    || self.block_heartbeat && self.last_emitted_block + 5 <= block.slot 
                     || self.last_non_empty_block == block.slot 
+                    || !rewards.is_empty()
                 {
                     self.last_emitted_block = block.slot;
-                    Some(render_block_message(&self.fields.block, block))
+                    self.mark_emitted(block.slot);
+                    Some(render_block_message(&self.fields, block, &rewards))
                 } else {
                     None
                 }
@@ -156,9 +471,456 @@ impl SubscriptionState {
                     None
                 } else {
                     self.last_non_empty_block = tx.slot;
+                    self.mark_emitted(tx.slot);
                     Some(render_transaction_message(&self.fields, tx, &selection))   
                 }
+            },
+            DataMessage::Account(acc) => {
+                if self.filter.eval_account(acc) {
+                    self.last_non_empty_block = acc.slot;
+                    self.mark_emitted(acc.slot);
+                    Some(render_account_message(&self.fields, acc))
+                } else {
+                    None
+                }
+            },
+            DataMessage::Rollback(rollback) => {
+                let slots: Vec<u64> = rollback.slots.iter()
+                    .copied()
+                    .filter(|slot| self.emitted_slots.remove(slot))
+                    .collect();
+                if slots.is_empty() {
+                    None
+                } else {
+                    Some(render_rollback_message(&slots))
+                }
+            },
+            DataMessage::SlotStatus(status) => {
+                if !self.emitted_slots.contains(&status.slot) {
+                    return None
+                }
+                if status.status == Commitment::Finalized {
+                    self.emitted_slots.remove(&status.slot);
+                }
+                if self.commitment_updates {
+                    Some(render_slot_status_message(status))
+                } else {
+                    None
+                }
             }
         }
     }
+
+    fn mark_emitted(&mut self, slot: u64) {
+        if self.emitted_slots.insert(slot) && self.emitted_slots.len() > MAX_EMITTED_SLOTS {
+            // forget the older half at once, so that the set is not scanned on every insert
+            let watermark = slot.saturating_sub(MAX_EMITTED_SLOTS as u64 / 2);
+            self.emitted_slots.retain(|emitted| *emitted >= watermark);
+        }
+    }
+}
+
+
+/// Tracks commitment levels of the transactions with the given signatures
+struct SignatureState {
+    /// Status of every signature, that hasn't been finalized yet
+    pending: HashMap<String, Option<SignatureStatus>>
+}
+
+
+struct SignatureStatus {
+    slot: u64,
+    commitment: Commitment,
+    err: Option<JsonString>
+}
+
+
+impl SignatureState {
+    fn new(signatures: Vec<String>) -> Self {
+        Self {
+            pending: signatures.into_iter().map(|sig| (sig, None)).collect()
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.pending.is_empty()
+    }
+
+    fn emit(&mut self, msg: &DataMessage) -> Vec<JsonString> {
+        let mut out = Vec::new();
+        match msg {
+            DataMessage::Transaction(tx) => {
+                let Some(signature) = tx.transaction.signature.as_deref() else {
+                    return out
+                };
+                let Some(status @ None) = self.pending.get_mut(signature) else {
+                    return out
+                };
+                let status = status.insert(SignatureStatus {
+                    slot: tx.slot,
+                    commitment: Commitment::Processed,
+                    err: tx.transaction.err.clone()
+                });
+                out.push(render_signature_message(signature, status.slot, status.commitment, status.err.as_deref()));
+            },
+            DataMessage::SlotStatus(upgrade) => {
+                for (signature, status) in self.pending.iter_mut() {
+                    let Some(status) = status.as_mut() else {
+                        continue
+                    };
+                    if status.slot != upgrade.slot || status.commitment >= upgrade.status {
+                        continue
+                    }
+                    status.commitment = upgrade.status;
+                    out.push(render_signature_message(signature, status.slot, status.commitment, status.err.as_deref()));
+                }
+                self.pending.retain(|_, status| {
+                    status.as_ref().is_none_or(|status| status.commitment != Commitment::Finalized)
+                });
+            },
+            DataMessage::Rollback(rollback) => {
+                // the transaction might land again in another slot
+                for status in self.pending.values_mut() {
+                    if status.as_ref().is_some_and(|status| rollback.slots.contains(&status.slot)) {
+                        *status = None;
+                    }
+                }
+            },
+            _ => {}
+        }
+        out
+    }
+
+    /// Looks up transactions, that might have been skipped
+    fn recover(&mut self, broadcast: &Broadcast) -> Vec<JsonString> {
+        let missing: Vec<Arc<DataMessage>> = self.pending.iter()
+            .filter(|(_, status)| status.is_none())
+            .filter_map(|(sig, _)| broadcast.get_transaction(sig).ok().flatten())
+            .collect();
+        missing.iter().flat_map(|msg| self.emit(msg)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::geyser::connect_mock_geyser;
+    use crate::ingest::{BufferOptions, Ingest, SourceMode, SourceOptions, SourceRole};
+    use serde_json::{json, Value};
+    use std::time::Duration;
+    use tokio::time::timeout;
+
+    #[tokio::test]
+    async fn subscription_output() {
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 0,
+            max_bytes: 0
+        });
+        let pushdown = FilterPushdown::new();
+        let rpc = build_rpc_module(broadcast.clone(), pushdown.clone());
+
+        let mut sub = rpc.subscribe_unbounded("spraySubscribe", [json!({
+            "fields": {
+                "block": {"number": true, "parentHash": true},
+                "transaction": {"transactionIndex": true}
+            },
+            "transactions": [{}],
+            "includeAllBlocks": true,
+            "commitmentUpdates": true
+        })]).await.unwrap();
+
+        let client = connect_mock_geyser(r#"
+            sessions:
+              - - slot: { slot: 10, parent: 9 }
+                - transaction: { slot: 10, index: 0 }
+                - transaction: { slot: 10, index: 1 }
+                - block: { slot: 10, executed_transaction_count: 2 }
+                - slot: { slot: 11, parent: 10 }
+                - block: { slot: 11 }
+                - slot: { slot: 10, parent: 9, status: confirmed }
+        "#).await;
+        let mut ingest = Ingest::new();
+        ingest.add_source("mock", client, SourceOptions {
+            votes: false,
+            accounts: false,
+            filter_pushdown: false,
+            ping_interval: Duration::ZERO,
+            idle_timeout: Duration::from_secs(10),
+            record: None,
+            mode: SourceMode::Transactions,
+            role: SourceRole::Primary
+        });
+        let _ingest = ingest.start(broadcast, pushdown);
+
+        let mut notifications = Vec::new();
+        for _ in 0..5 {
+            let (notification, _) = timeout(Duration::from_secs(5), sub.next::<Value>()).await
+                .expect("no notification was received")
+                .unwrap()
+                .unwrap();
+            notifications.push(notification);
+        }
+        assert_eq!(notifications, [
+            json!({"type": "transaction", "slot": 10, "transactionIndex": 0, "transaction": {"transactionIndex": 0}}),
+            json!({"type": "transaction", "slot": 10, "transactionIndex": 1, "transaction": {"transactionIndex": 1}}),
+            json!({
+                "type": "block",
+                "slot": 10,
+                "header": {"number": 10, "parentHash": "c8fpTXm3XTRgE5maYQ24Li4L65wMYvAFomzXknxVEx7"}
+            }),
+            json!({
+                "type": "block",
+                "slot": 11,
+                "header": {"number": 11, "parentHash": "g35TxFqwMx95vCk63fTxGTHb6ei4W24qg5t2x6xD3cT"}
+            }),
+            json!({"type": "commitment", "slot": 10, "status": "confirmed"})
+        ]);
+    }
+
+    #[tokio::test]
+    async fn gap_notifications() {
+        use crate::ingest::testing::{data, tx};
+
+        let transactions: Vec<_> = (9..20).map(|slot| data(tx(slot, 0))).collect();
+        let notification = |slot| json!({"type": "transaction", "slot": slot, "transactionIndex": 0, "transaction": {}});
+
+        for lag_policy in ["notify", "disconnect"] {
+            let broadcast = Broadcast::new(4, BufferOptions {
+                max_slots: 0,
+                max_bytes: 0
+            });
+            let rpc = build_rpc_module(broadcast.clone(), FilterPushdown::new());
+            let mut sub = rpc.subscribe_unbounded("spraySubscribe", [json!({
+                "fields": {},
+                "transactions": [{}],
+                "lagPolicy": lag_policy
+            })]).await.unwrap();
+            let mut next = async || -> Option<Value> {
+                let notification = timeout(Duration::from_secs(5), sub.next::<Value>()).await
+                    .expect("no notification was received");
+                notification.map(|res| res.unwrap().0)
+            };
+
+            broadcast.send(Commitment::Processed, transactions[0].clone());
+            assert_eq!(next().await, Some(notification(9)));
+
+            // the subscription task doesn't run, until the test yields
+            for msg in transactions[1..].iter() {
+                broadcast.send(Commitment::Processed, msg.clone());
+            }
+            assert_eq!(
+                next().await,
+                Some(json!({"type": "gap", "skipped": 6, "fromSlot": 9, "toSlot": 16}))
+            );
+            if lag_policy == "disconnect" {
+                assert_eq!(next().await, Some(Value::Null));
+                assert_eq!(next().await, None, "subscription was not closed");
+            } else {
+                for slot in 16..20 {
+                    assert_eq!(next().await, Some(notification(slot)));
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn emitted_slots_window() {
+        use crate::data::RollbackData;
+        use crate::ingest::testing::{block, data};
+
+        let mut state = SubscriptionState::new(serde_json::from_value(json!({
+            "fields": {},
+            "includeAllBlocks": true
+        })).unwrap());
+        for slot in 1..2000 {
+            assert!(state.emit(&data(block(slot, slot - 1))).is_some());
+        }
+        assert!(state.emitted_slots.len() <= MAX_EMITTED_SLOTS);
+
+        let rollback = state.emit(&DataMessage::Rollback(RollbackData {
+            slots: vec![10, 1998, 1999]
+        }));
+        assert_eq!(rollback.as_deref(), Some(r#"{"type":"rollback","slots":[1998,1999]}"#));
+        assert!(!state.emitted_slots.contains(&1999));
+    }
+
+    #[tokio::test]
+    async fn resume_across_buffer_seam() {
+        use crate::ingest::testing::{data, tx};
+
+        const SLOTS: u64 = 2000;
+        let transactions: Vec<_> = (1..=SLOTS).map(|slot| data(tx(slot, 0))).collect();
+
+        let broadcast = Broadcast::new(SLOTS as usize, BufferOptions {
+            max_slots: SLOTS,
+            max_bytes: usize::MAX
+        });
+        let rpc = build_rpc_module(broadcast.clone(), FilterPushdown::new());
+
+        // publish concurrently with the subscription
+        let (started_tx, started_rx) = std::sync::mpsc::channel();
+        let publisher = std::thread::spawn(move || {
+            for (i, msg) in transactions.into_iter().enumerate() {
+                broadcast.send(Commitment::Processed, msg);
+                if i == 100 {
+                    started_tx.send(()).unwrap();
+                }
+                std::thread::yield_now();
+            }
+        });
+        started_rx.recv().unwrap();
+
+        let mut sub = rpc.subscribe_unbounded("spraySubscribe", [json!({
+            "fields": {},
+            "transactions": [{}],
+            "fromSlot": 1
+        })]).await.unwrap();
+        publisher.join().unwrap();
+
+        for slot in 1..=SLOTS {
+            let (notification, _) = timeout(Duration::from_secs(5), sub.next::<Value>()).await
+                .expect("no notification was received")
+                .unwrap()
+                .unwrap();
+            assert_eq!(notification["slot"], slot);
+        }
+    }
+
+    #[test]
+    fn one_shot_query() {
+        use crate::data::RollbackData;
+        use crate::ingest::testing::{block, data, tx};
+
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 100,
+            max_bytes: usize::MAX
+        });
+        for slot in 10..20 {
+            if [10, 11, 12, 18, 19].contains(&slot) {
+                broadcast.send(Commitment::Processed, data(tx(slot, 0)));
+            }
+            broadcast.send(Commitment::Processed, data(block(slot, slot - 1)));
+        }
+        broadcast.send(Commitment::Processed, Arc::new(DataMessage::Rollback(RollbackData {
+            slots: vec![11]
+        })));
+
+        let query = |from_block: u64| -> SolanaQuery {
+            serde_json::from_value(json!({
+                "fields": {"block": {"number": true}, "transaction": {"transactionIndex": true}},
+                "transactions": [{}],
+                "fromBlock": from_block
+            })).unwrap()
+        };
+        let run = |from_block, max_size| -> (Vec<String>, u64) {
+            let json = run_one_shot_query(&broadcast, query(from_block), max_size).unwrap();
+            let res: Value = serde_json::from_str(&json).unwrap();
+            let data = res["data"].as_array().unwrap().iter().map(|msg| {
+                format!("{} {}", msg["type"].as_str().unwrap(), msg["slot"])
+            }).collect();
+            (data, res["lastBlock"].as_u64().unwrap())
+        };
+
+        // blocks without matches are not included, rolled back slots are excluded
+        assert_eq!(run(10, 10_000), (
+            vec![
+                "transaction 10".to_string(), "block 10".to_string(),
+                "transaction 12".to_string(), "block 12".to_string(),
+                "transaction 18".to_string(), "block 18".to_string(),
+                "transaction 19".to_string(), "block 19".to_string()
+            ],
+            19
+        ));
+
+        // results are cut at a slot boundary
+        let (data, last_block) = run(11, 250);
+        assert_eq!(data, ["transaction 12", "block 12"]);
+        assert_eq!(last_block, 17);
+        assert_eq!(run(last_block + 1, 250), (vec!["transaction 18".to_string(), "block 18".to_string()], 18));
+
+        assert!(run_one_shot_query(&broadcast, query(10), 10).is_err());
+    }
+
+    #[tokio::test]
+    async fn get_transaction() {
+        use crate::ingest::testing::{data, tx};
+
+        let msg = data(tx(10, 0));
+        let DataMessage::Transaction(transaction) = msg.as_ref() else {
+            unreachable!()
+        };
+        let signature = transaction.transaction.signature.clone().unwrap();
+        let fields = json!({"transaction": {"transactionIndex": true}});
+
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 10,
+            max_bytes: usize::MAX
+        });
+        broadcast.send(Commitment::Processed, msg.clone());
+        let rpc = build_rpc_module(broadcast, FilterPushdown::new());
+
+        let res: Value = rpc.call("sprayGetTransaction", (signature.clone(), fields.clone())).await.unwrap();
+        assert_eq!(res["slot"], 10);
+        assert_eq!(res["transaction"], json!({"transactionIndex": 0}));
+
+        let res: Value = rpc.call("sprayGetTransaction", ("unknown", fields)).await.unwrap();
+        assert!(res.is_null());
+
+        // lookups fail instead of returning `null`, when there is no buffer
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 0,
+            max_bytes: 0
+        });
+        broadcast.send(Commitment::Processed, msg);
+        let rpc = build_rpc_module(broadcast, FilterPushdown::new());
+        assert!(rpc.call::<_, Value>("sprayGetTransaction", [signature]).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn signature_published_before_subscription() {
+        use crate::data::SlotStatusData;
+        use crate::ingest::testing::{block, data, tx};
+
+        let msg = data(tx(10, 0));
+        let DataMessage::Transaction(transaction) = msg.as_ref() else {
+            unreachable!()
+        };
+        let signature = transaction.transaction.signature.clone().unwrap();
+
+        let broadcast = Broadcast::new(100, BufferOptions {
+            max_slots: 10,
+            max_bytes: usize::MAX
+        });
+        broadcast.send(Commitment::Processed, msg.clone());
+        broadcast.send(Commitment::Processed, data(block(10, 9)));
+        let rpc = build_rpc_module(broadcast.clone(), FilterPushdown::new());
+
+        let mut sub = rpc.subscribe_unbounded(
+            "spraySignatureSubscribe",
+            [json!({"signatures": [signature]})]
+        ).await.unwrap();
+        let mut next = async || -> Value {
+            let (notification, _) = timeout(Duration::from_secs(5), sub.next::<Value>()).await
+                .expect("no notification was received")
+                .unwrap()
+                .unwrap();
+            notification
+        };
+
+        let notification = |status| json!({
+            "type": "signature",
+            "signature": signature,
+            "slot": 10,
+            "status": status,
+            "err": null
+        });
+        assert_eq!(next().await, notification("processed"));
+
+        broadcast.send(Commitment::Processed, Arc::new(DataMessage::SlotStatus(SlotStatusData {
+            slot: 10,
+            status: Commitment::Finalized
+        })));
+        assert_eq!(next().await, notification("finalized"));
+        assert!(next().await.is_null());
+    }
 }
\ No newline at end of file